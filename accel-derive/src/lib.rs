@@ -26,6 +26,7 @@ mod builder;
 mod contexted;
 mod host;
 mod parser;
+mod scalar;
 
 use proc_macro::TokenStream;
 
@@ -40,3 +41,27 @@ pub fn kernel(_attr: TokenStream, func: TokenStream) -> TokenStream {
 pub fn contexted(input: TokenStream) -> TokenStream {
     contexted::contexted(syn::parse(input).unwrap()).into()
 }
+
+/// Implement `accel::Scalar` for a `#[repr(C)]` struct whose named fields have the same `Scalar` type
+///
+/// Arithmetic operations are implemented component-wise,
+/// and the struct is regarded as a multi-channel element of the field type in CUDA arrays.
+/// The number of fields must be 1, 2 or 4 as the number of channels of CUDA arrays.
+///
+/// ```
+/// use accel::*;
+///
+/// #[repr(C)]
+/// #[derive(Debug, Clone, Copy, PartialEq, Scalar)]
+/// struct Complex {
+///     re: f32,
+///     im: f32,
+/// }
+///
+/// assert_eq!(Complex::size_of(), 8);
+/// assert_eq!(Complex::channels(), 2);
+/// ```
+#[proc_macro_derive(Scalar)]
+pub fn scalar(input: TokenStream) -> TokenStream {
+    scalar::scalar(syn::parse(input).unwrap()).into()
+}
@@ -0,0 +1,120 @@
+use proc_macro2::*;
+use quote::quote;
+use syn::*;
+
+fn is_repr_c(input: &DeriveInput) -> bool {
+    input.attrs.iter().any(|attr| match attr.parse_meta() {
+        Ok(Meta::List(list)) if list.path.is_ident("repr") => list.nested.iter().any(
+            |nested| matches!(nested, NestedMeta::Meta(Meta::Path(path)) if path.is_ident("C")),
+        ),
+        _ => false,
+    })
+}
+
+fn named_fields(input: &DeriveInput) -> Vec<Field> {
+    match &input.data {
+        syn::Data::Struct(syn::DataStruct { fields, .. }) => match fields {
+            Fields::Named(fields_named) => fields_named.named.iter().cloned().collect(),
+            _ => unreachable!("Must be named field"),
+        },
+        _ => unreachable!("Must be a struct"),
+    }
+}
+
+pub fn scalar(input: DeriveInput) -> TokenStream {
+    let name = &input.ident;
+    assert!(is_repr_c(&input), "Scalar struct must be #[repr(C)]");
+    assert!(
+        input.generics.params.is_empty(),
+        "Scalar struct must not be generic"
+    );
+    let fields = named_fields(&input);
+    assert!(
+        matches!(fields.len(), 1 | 2 | 4),
+        "Scalar struct must have 1, 2 or 4 fields since CUDA arrays support only these numbers of channels"
+    );
+    let idents: Vec<Ident> = fields.iter().map(|f| f.ident.clone().unwrap()).collect();
+    // All fields must have the same type as the first one,
+    // which is checked by the compiler since `splat` sets a value of this type to every field
+    let ty = &fields[0].ty;
+    let num_fields = fields.len() as u32;
+    let splat = |value: TokenStream| quote! { #name { #(#idents: #value),* } };
+    let zero = splat(quote!(<#ty as ::accel::num_traits::Zero>::zero()));
+    let one = splat(quote!(<#ty as ::accel::num_traits::One>::one()));
+    let parsed = splat(quote!(value));
+
+    let ops = [
+        (quote!(Add), quote!(add)),
+        (quote!(Sub), quote!(sub)),
+        (quote!(Mul), quote!(mul)),
+        (quote!(Div), quote!(div)),
+        (quote!(Rem), quote!(rem)),
+    ]
+    .iter()
+    .map(|(op, method)| {
+        quote! {
+            impl ::std::ops::#op for #name {
+                type Output = Self;
+                fn #method(self, rhs: Self) -> Self {
+                    #name {
+                        #(#idents: ::std::ops::#op::#method(self.#idents, rhs.#idents)),*
+                    }
+                }
+            }
+        }
+    })
+    .collect::<Vec<_>>();
+
+    // Structs of 1, 2 or 4 bytes are set by `cuMemsetD8/16/32`, which takes the bit pattern as an integer
+    let to_le = [quote!(u8), quote!(u16), quote!(u32)]
+        .iter()
+        .map(|le| {
+            let method = Ident::new(&format!("to_le_{}", le), Span::call_site());
+            quote! {
+                fn #method(self) -> Option<#le> {
+                    if ::std::mem::size_of::<Self>() == ::std::mem::size_of::<#le>() {
+                        Some(unsafe { ::std::ptr::read_unaligned(&self as *const Self as *const #le) })
+                    } else {
+                        None
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    quote! {
+        #(#ops)*
+
+        impl ::accel::num_traits::Zero for #name {
+            fn zero() -> Self {
+                #zero
+            }
+            fn is_zero(&self) -> bool {
+                #(::accel::num_traits::Zero::is_zero(&self.#idents))&&*
+            }
+        }
+
+        impl ::accel::num_traits::One for #name {
+            fn one() -> Self {
+                #one
+            }
+        }
+
+        impl ::accel::num_traits::Num for #name {
+            type FromStrRadixErr = <#ty as ::accel::num_traits::Num>::FromStrRadixErr;
+            fn from_str_radix(s: &str, radix: u32) -> ::std::result::Result<Self, Self::FromStrRadixErr> {
+                <#ty as ::accel::num_traits::Num>::from_str_radix(s, radix).map(|value| #parsed)
+            }
+        }
+
+        impl ::accel::Scalar for #name {
+            fn format() -> ::accel::ArrayFormatTag {
+                <#ty as ::accel::Scalar>::format()
+            }
+            fn channels() -> u32 {
+                #num_fields * <#ty as ::accel::Scalar>::channels()
+            }
+            #(#to_le)*
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Quick benchmarks for checking the environment, e.g. PCIe/NVLink bandwidth
+
+use crate::{contexted_call, error::*, *};
+use cuda::*;
+
+/// Bandwidth of memory transfer between host and device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandwidthReport {
+    /// Size of transferred data in bytes
+    pub bytes: usize,
+    /// Host to Device bandwidth in GB/s
+    pub host_to_device: f64,
+    /// Device to Host bandwidth in GB/s
+    pub device_to_host: f64,
+}
+
+/// Measure bandwidth of H2D and D2H transfers using page-locked host memory
+///
+/// Each transfer is timed by CUDA events recorded on a stream.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let report = benchmark::measure_bandwidth(&ctx, 1 << 20).unwrap();
+/// println!("H2D: {} GB/s", report.host_to_device);
+/// println!("D2H: {} GB/s", report.device_to_host);
+/// ```
+///
+/// Panic
+/// ------
+/// - if `bytes` is zero
+pub fn measure_bandwidth(ctx: &Context, bytes: usize) -> Result<BandwidthReport> {
+    let mut host = PageLockedMemory::<u8>::zeros(ctx, bytes);
+    let mut device = DeviceMemory::<u8>::zeros(ctx, bytes);
+    let mut stream = Stream::new(ctx);
+    let mut start = Event::new(ctx.clone());
+    let mut end = Event::new(ctx.clone());
+
+    let mut measure = |dst: CUdeviceptr, src: CUdeviceptr| -> Result<f64> {
+        start.record(&mut stream);
+        unsafe { contexted_call!(ctx, cuMemcpyAsync, dst, src, bytes, stream.stream) }?;
+        end.record(&mut stream);
+        end.sync()?;
+        let ms = start.elapsed_time(&end)?;
+        Ok(bytes as f64 / (ms as f64 * 1e-3) / 1e9)
+    };
+
+    let host_to_device = measure(
+        device.head_addr_mut() as CUdeviceptr,
+        host.head_addr() as CUdeviceptr,
+    )?;
+    let device_to_host = measure(
+        host.head_addr_mut() as CUdeviceptr,
+        device.head_addr() as CUdeviceptr,
+    )?;
+    Ok(BandwidthReport {
+        bytes,
+        host_to_device,
+        device_to_host,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_bandwidth() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let report = super::measure_bandwidth(&ctx, 1 << 20)?;
+        dbg!(&report);
+        assert!(report.host_to_device > 0.0);
+        assert!(report.device_to_host > 0.0);
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+//! Utilities for debugging CUDA state
+
+use crate::{error::*, *};
+use cuda::*;
+
+/// Snapshot of the context stack of the current thread as raw pointers
+///
+/// The first element is the bottom of the stack, and the last one is the current context.
+/// The CUDA driver does not provide an API to inspect the stack,
+/// so this pops all contexts and pushes them back in the original order.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let before = debug::context_stack().unwrap();
+/// let _g = ctx.guard().unwrap();
+/// let after = debug::context_stack().unwrap();
+/// assert_eq!(after.len(), before.len() + 1);
+/// ```
+pub fn context_stack() -> Result<Vec<usize>> {
+    let mut popped = Vec::new();
+    let result = pop_all(&mut popped);
+    for &ptr in popped.iter().rev() {
+        unsafe { ffi_call!(cuCtxPushCurrent_v2, ptr) }?;
+    }
+    result?;
+    Ok(popped.into_iter().rev().map(|ptr| ptr as usize).collect())
+}
+
+/// Pop contexts until the stack becomes empty, where the top of the stack comes first
+fn pop_all(popped: &mut Vec<CUcontext>) -> Result<()> {
+    loop {
+        let current = unsafe { ffi_new!(cuCtxGetCurrent) }?;
+        if current.is_null() {
+            return Ok(());
+        }
+        popped.push(unsafe { ffi_new!(cuCtxPopCurrent_v2) }?);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context_stack() -> Result<()> {
+        let device = Device::nth(0)?;
+        let a = device.create_context();
+        let b = device.create_context();
+        let base = super::context_stack()?;
+
+        let _ga = a.guard()?;
+        let pa = unsafe { ffi_new!(cuCtxGetCurrent) }? as usize;
+        let _gb = b.guard()?;
+        let pb = unsafe { ffi_new!(cuCtxGetCurrent) }? as usize;
+        assert_ne!(pa, pb);
+
+        let stack = super::context_stack()?;
+        assert_eq!(stack.len(), base.len() + 2);
+        assert_eq!(&stack[..base.len()], base.as_slice());
+        assert_eq!(&stack[base.len()..], &[pa, pb]);
+        // The stack is restored after the snapshot
+        assert_eq!(unsafe { ffi_new!(cuCtxGetCurrent) }? as usize, pb);
+        Ok(())
+    }
+}
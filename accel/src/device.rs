@@ -5,28 +5,40 @@
 
 use crate::{error::*, *};
 use cuda::*;
-use std::sync::{Arc, Once};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock, Weak},
+    thread::{self, ThreadId},
+};
 
 pub use accel_derive::Contexted;
 
+/// Initialize CUDA Driver API
+///
+/// This calls [cuInit] only once in the process, and returns the cached result for later calls.
+/// It is called automatically in [Device::get_count] and [Device::nth],
+/// i.e. users do not need to call it explicitly.
+///
+/// [cuInit]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__INITIALIZE.html
+/// [Device::get_count]: ./struct.Device.html#method.get_count
+/// [Device::nth]: ./struct.Device.html#method.nth
+pub fn init() -> Result<()> {
+    static DRIVER_API_INIT: OnceLock<Result<()>> = OnceLock::new();
+    DRIVER_API_INIT
+        .get_or_init(|| unsafe { ffi_call!(cuda::cuInit, 0) })
+        .clone()
+}
+
 /// Handler for device and its primary context
 #[derive(Debug, PartialEq, PartialOrd)]
 pub struct Device {
-    device: CUdevice,
+    pub(crate) device: CUdevice,
 }
 
 impl Device {
-    /// Initializer for CUDA Driver API
-    fn init() {
-        static DRIVER_API_INIT: Once = Once::new();
-        DRIVER_API_INIT.call_once(|| unsafe {
-            ffi_call!(cuda::cuInit, 0).expect("Initialization of CUDA Driver API failed");
-        });
-    }
-
     /// Get number of available GPUs
     pub fn get_count() -> Result<usize> {
-        Self::init();
+        init()?;
         let mut count: i32 = 0;
         unsafe {
             ffi_call!(cuDeviceGetCount, &mut count as *mut i32)?;
@@ -66,6 +78,74 @@ impl Device {
         Ok(String::from_utf8(bytes).expect("GPU name is not UTF8"))
     }
 
+    /// Check if the device shares a unified address space with the host
+    ///
+    /// The classification of pointers by [Memory::memory_type] assumes unified addressing,
+    /// i.e. host and device memories are placed in a single 64-bit address space.
+    ///
+    /// [Memory::memory_type]: ../memory/trait.Memory.html#tymethod.memory_type
+    pub fn unified_addressing(&self) -> Result<bool> {
+        let value = unsafe {
+            ffi_new!(
+                cuDeviceGetAttribute,
+                CUdevice_attribute::CU_DEVICE_ATTRIBUTE_UNIFIED_ADDRESSING,
+                self.device
+            )
+        }?;
+        Ok(value != 0)
+    }
+
+    /// Check if the device can access managed memory concurrently with the host
+    ///
+    /// Without this capability, e.g. on pre-Pascal GPUs, the host must not access
+    /// managed memory, e.g. [DeviceMemory], while any kernel is running on the device
+    /// unless the memory is attached to another stream by [DeviceMemory::attach_stream].
+    ///
+    /// [DeviceMemory]: ../memory/struct.DeviceMemory.html
+    /// [DeviceMemory::attach_stream]: ../memory/struct.DeviceMemory.html#method.attach_stream
+    pub fn concurrent_managed_access(&self) -> Result<bool> {
+        let value = unsafe {
+            ffi_new!(
+                cuDeviceGetAttribute,
+                CUdevice_attribute::CU_DEVICE_ATTRIBUTE_CONCURRENT_MANAGED_ACCESS,
+                self.device
+            )
+        }?;
+        Ok(value != 0)
+    }
+
+    /// Check if this device is capable of accessing memory on `peer` directly
+    ///
+    /// The access must be enabled by `cuCtxEnablePeerAccess` before accessing.
+    pub fn can_access_peer(&self, peer: &Device) -> Result<bool> {
+        let value = unsafe { ffi_new!(cuDeviceCanAccessPeer, self.device, peer.device) }?;
+        Ok(value != 0)
+    }
+
+    /// Destroy all allocations and reset all state on the primary context of this device
+    ///
+    /// Every memory, stream, module and so on allocated on the primary context,
+    /// including those allocated by other libraries (e.g. CUDA Runtime API users) in this process,
+    /// becomes invalid immediately.
+    /// Contexts created by [Device::create_context] are not affected.
+    ///
+    /// This is intended to isolate test cases from each other.
+    ///
+    /// Safety
+    /// ------
+    /// - No object on the primary context, e.g. a context wrapped by [Device::current_context]
+    ///   or a stream wrapped by [Stream::from_raw], may be used after this call
+    /// - Other threads must not use the primary context concurrently,
+    ///   e.g. tests calling this must be run serially by `--test-threads=1`
+    ///
+    /// [Device::create_context]: ./struct.Device.html#method.create_context
+    /// [Device::current_context]: ./struct.Device.html#method.current_context
+    /// [Stream::from_raw]: ../stream/struct.Stream.html#method.from_raw
+    pub unsafe fn reset_primary_context(&self) -> Result<()> {
+        ffi_call!(cuDevicePrimaryCtxReset, self.device)?;
+        Ok(())
+    }
+
     /// Create a new CUDA context on this device.
     ///
     /// ```
@@ -74,6 +154,10 @@ impl Device {
     /// let ctx = device.create_context();
     /// ```
     pub fn create_context(&self) -> Context {
+        self.create_context_with(&mut registry().lock().unwrap())
+    }
+
+    fn create_context_with(&self, registry: &mut Registry) -> Context {
         let ptr = unsafe {
             ffi_new!(
                 cuCtxCreate_v2,
@@ -87,11 +171,100 @@ impl Device {
         }
         let ptr_new = ctx_pop().unwrap();
         assert_eq!(ptr, ptr_new);
-        Arc::new(ContextOwned { ptr })
+        let ctx = Arc::new(ContextOwned { ptr, owned: true });
+        registry.contexts.insert(ptr as usize, Arc::downgrade(&ctx));
+        ctx
     }
+
+    /// Get the context current on this thread if accel owns it, or a context shared on this device
+    ///
+    /// This avoids creating many contexts accidentally, e.g. in a library called
+    /// from an application which already uses a context on the device.
+    ///
+    /// - If the current context of this thread is on this device and
+    ///   has been created by accel, e.g. [Device::create_context], a clone of it is returned.
+    /// - Otherwise, a context shared by the calls on this device is returned.
+    ///   It is created at the first call, and kept while any clone of it exists.
+    ///
+    /// The context stack of this thread is not modified.
+    /// Use [Device::current_context] to wrap a context created outside of accel.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// let device = Device::nth(0).unwrap();
+    /// let ctx = device.current_or_create_context().unwrap();
+    /// let current = device.current_or_create_context().unwrap();
+    /// assert_eq!(ctx, current);
+    /// ```
+    ///
+    /// [Device::create_context]: ./struct.Device.html#method.create_context
+    /// [Device::current_context]: ./struct.Device.html#method.current_context
+    pub fn current_or_create_context(&self) -> Result<Context> {
+        let ptr = unsafe { ffi_new!(cuCtxGetCurrent) }?;
+        if !ptr.is_null() && ctx_device(ptr)? == *self {
+            if let Some(ctx) = registry().lock().unwrap().get(ptr) {
+                return Ok(ctx);
+            }
+        }
+        let mut registry = registry().lock().unwrap();
+        if let Some(ctx) = registry.shared.get(&self.device).and_then(Weak::upgrade) {
+            return Ok(ctx);
+        }
+        let ctx = self.create_context_with(&mut registry);
+        registry.shared.insert(self.device, Arc::downgrade(&ctx));
+        Ok(ctx)
+    }
+
+    /// Wrap the context current on this thread if it is on this device
+    ///
+    /// A context created by accel is returned as a clone.
+    /// Otherwise, the context is wrapped without ownership, i.e. it is not destroyed by `Drop`.
+    /// Returns `None` if no context on this device is current.
+    ///
+    /// Safety
+    /// ------
+    /// - A context not created by accel must not be destroyed by its owner
+    ///   while the returned context or any clone of it exists.
+    pub unsafe fn current_context(&self) -> Result<Option<Context>> {
+        let ptr = ffi_new!(cuCtxGetCurrent)?;
+        if ptr.is_null() || ctx_device(ptr)? != *self {
+            return Ok(None);
+        }
+        if let Some(ctx) = registry().lock().unwrap().get(ptr) {
+            return Ok(Some(ctx));
+        }
+        Ok(Some(Arc::new(ContextOwned { ptr, owned: false })))
+    }
+}
+
+/// Contexts created by accel, looked up by [Device::current_or_create_context]
+///
+/// [Device::current_or_create_context]: ./struct.Device.html#method.current_or_create_context
+#[derive(Default)]
+struct Registry {
+    /// All contexts created by accel, keyed by their pointers
+    contexts: HashMap<usize, Weak<ContextOwned>>,
+    /// Context shared by [Device::current_or_create_context] for each device
+    ///
+    /// [Device::current_or_create_context]: ./struct.Device.html#method.current_or_create_context
+    shared: HashMap<CUdevice, Weak<ContextOwned>>,
+}
+
+impl Registry {
+    fn get(&self, ptr: CUcontext) -> Option<Context> {
+        self.contexts.get(&(ptr as usize)).and_then(Weak::upgrade)
+    }
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(Default::default)
 }
 
 /// Push to the context stack of this thread
+///
+/// This uses the context stack managed by the CUDA driver, and nested push/pop works in LIFO order.
+/// The same context can be pushed again while it is already on the stack.
 fn ctx_push(ptr: CUcontext) -> Result<()> {
     unsafe { ffi_call!(cuCtxPushCurrent_v2, ptr) }?;
     Ok(())
@@ -113,6 +286,33 @@ fn ctx_version(ptr: CUcontext) -> Result<u32> {
     Ok(version)
 }
 
+/// Check the context has not been destroyed
+///
+/// A context may be destroyed while it is referred, e.g. by [ContextRef] or
+/// a context wrapped by [Device::current_context], and pushing it causes an opaque error.
+/// This check costs an additional API call, and is done only in debug build.
+///
+/// [Device::current_context]: ./struct.Device.html#method.current_context
+#[cfg(debug_assertions)]
+fn ctx_check(ptr: CUcontext) -> Result<()> {
+    match ctx_version(ptr) {
+        Err(AccelError::CUDAError {
+            error: cudaError_enum::CUDA_ERROR_INVALID_CONTEXT,
+            ..
+        }) => Err(AccelError::InvalidContext),
+        result => result.map(|_| ()),
+    }
+}
+
+/// Get the device of the context
+fn ctx_device(ptr: CUcontext) -> Result<Device> {
+    ctx_push(ptr)?;
+    let device = unsafe { ffi_new!(cuCtxGetDevice) };
+    let ptr_new = ctx_pop()?;
+    assert_eq!(ptr, ptr_new);
+    Ok(Device { device: device? })
+}
+
 /// Block until all tasks in this context to be complete.
 fn ctx_sync(ptr: CUcontext) -> Result<()> {
     ctx_push(ptr)?;
@@ -130,17 +330,44 @@ pub trait Contexted {
 }
 
 /// Owend handler for CUDA context
-#[derive(Debug, PartialEq)]
+///
+/// A context wrapped by [Device::current_context] is not owned,
+/// and is not destroyed by `Drop`.
+///
+/// [Device::current_context]: ./struct.Device.html#method.current_context
+#[derive(Debug)]
 pub struct ContextOwned {
     ptr: CUcontext,
+    /// The context is destroyed in `Drop` only if it is owned
+    owned: bool,
 }
 
 pub type Context = Arc<ContextOwned>;
 
+impl PartialEq for ContextOwned {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
 impl Drop for ContextOwned {
     fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
+        // The entry may have been replaced by a new context at the same address
+        if let Ok(mut registry) = registry().lock() {
+            let key = self.ptr as usize;
+            if registry
+                .contexts
+                .get(&key)
+                .is_some_and(|w| w.strong_count() == 0)
+            {
+                registry.contexts.remove(&key);
+            }
+        }
         if let Err(e) = unsafe { ffi_call!(cuCtxDestroy_v2, self.ptr) } {
-            log::error!("Context remove failed: {:?}", e);
+            report_error("Context remove failed", &e);
         }
     }
 }
@@ -158,6 +385,8 @@ impl Contexted for Context {
     }
 
     fn guard(&self) -> Result<ContextGuard> {
+        #[cfg(debug_assertions)]
+        ctx_check(self.ptr)?;
         ctx_push(self.ptr)?;
         Ok(ContextGuard { ptr: self.ptr })
     }
@@ -172,6 +401,11 @@ impl ContextOwned {
     pub fn get_ref(&self) -> ContextRef {
         ContextRef { ptr: self.ptr }
     }
+
+    /// Get the device of this context
+    pub fn device(&self) -> Result<Device> {
+        ctx_device(self.ptr)
+    }
 }
 
 /// Non-Owend handler for CUDA context
@@ -192,6 +426,14 @@ impl ContextRef {
         assert!(!ptr.is_null());
         ContextRef { ptr }
     }
+
+    /// Get the device of the referred context
+    ///
+    /// This is useful to find where a memory exists from its pointer,
+    /// since only the reference is available in this case.
+    pub fn device(&self) -> Result<Device> {
+        ctx_device(self.ptr)
+    }
 }
 
 unsafe impl Send for ContextRef {}
@@ -207,6 +449,8 @@ impl Contexted for ContextRef {
     }
 
     fn guard(&self) -> Result<ContextGuard> {
+        #[cfg(debug_assertions)]
+        ctx_check(self.ptr)?;
         ctx_push(self.ptr)?;
         Ok(ContextGuard { ptr: self.ptr })
     }
@@ -243,16 +487,83 @@ impl Drop for ContextGuard {
                 }
             }
             Err(e) => {
-                log::error!("Failed to pop context: {}", e);
+                report_error("Failed to pop context", &e);
             }
         }
     }
 }
 
+/// Contexts created lazily for each pair of a thread and a device
+///
+/// A context must be current on the thread using it, and
+/// tasks running on a thread pool cannot know which context is available on the thread.
+/// This pool creates a context for each thread and device at the first use,
+/// and [ContextPool::with_device] makes it current while the task runs.
+/// Contexts are kept until the pool is dropped, even after their threads exit.
+///
+/// ```
+/// # use accel::*;
+/// let pool = ContextPool::new();
+/// std::thread::scope(|s| {
+///     for _ in 0..2 {
+///         s.spawn(|| {
+///             let device = Device::nth(0).unwrap();
+///             pool.with_device(&device, |ctx| {
+///                 let _mem = DeviceMemory::<f32>::zeros(ctx, 12);
+///             });
+///         });
+///     }
+/// });
+/// assert_eq!(pool.len(), 2);
+/// ```
+///
+/// [ContextPool::with_device]: ./struct.ContextPool.html#method.with_device
+#[derive(Default)]
+pub struct ContextPool {
+    contexts: Mutex<HashMap<(ThreadId, CUdevice), Context>>,
+}
+
+impl ContextPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Call `f` with the context of `device` for the current thread pushed
+    ///
+    /// The context is created at the first call on each thread, and reused in later calls.
+    pub fn with_device<R>(&self, device: &Device, f: impl FnOnce(&Context) -> R) -> R {
+        let ctx = self
+            .contexts
+            .lock()
+            .unwrap()
+            .entry((thread::current().id(), device.device))
+            .or_insert_with(|| device.create_context())
+            .clone();
+        let _guard = ctx.guard().expect("Failed to push context");
+        f(&ctx)
+    }
+
+    /// Number of contexts created in this pool
+    pub fn len(&self) -> usize {
+        self.contexts.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn init_twice() -> Result<()> {
+        init()?;
+        init()?;
+        Ok(())
+    }
+
     #[test]
     fn get_count() -> Result<()> {
         Device::get_count()?;
@@ -265,12 +576,132 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn unified_addressing() -> Result<()> {
+        let device = Device::nth(0)?;
+        device.unified_addressing()?;
+        Ok(())
+    }
+
+    #[test]
+    fn current_or_create_context() -> Result<()> {
+        let device = Device::nth(0)?;
+        let a = device.current_or_create_context()?;
+        let b = device.current_or_create_context()?;
+        assert_eq!(a.ptr, b.ptr);
+        // The context stack of this thread is not modified
+        assert!(unsafe { device.current_context() }?.is_none());
+
+        // The current context created by accel is shared
+        let ctx = device.create_context();
+        let _g = ctx.guard()?;
+        let c = device.current_or_create_context()?;
+        assert_eq!(c.ptr, ctx.ptr);
+        drop(ctx);
+        // `c` keeps the context alive
+        c.sync()?;
+        Ok(())
+    }
+
+    #[test]
+    fn concurrent_managed_access() -> Result<()> {
+        let device = Device::nth(0)?;
+        device.concurrent_managed_access()?;
+        Ok(())
+    }
+
+    #[test]
+    fn error_location() -> Result<()> {
+        init()?;
+        match unsafe { ffi_new!(cuDeviceGet, -1) } {
+            Err(AccelError::CUDAError {
+                api_name, location, ..
+            }) => {
+                assert_eq!(api_name, "cuDeviceGet");
+                assert!(location.starts_with(file!()));
+            }
+            _ => panic!("cuDeviceGet(-1) must fail"),
+        }
+        Ok(())
+    }
+
     #[test]
     fn out_of_range() -> Result<()> {
         assert!(Device::nth(129).is_err());
         Ok(())
     }
 
+    // Resetting the primary context breaks other tests using it concurrently.
+    // Run serially by `cargo test -- --ignored --test-threads=1 reset_primary_context`
+    #[ignore]
+    #[test]
+    fn reset_primary_context() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = unsafe { ffi_new!(cuDevicePrimaryCtxRetain, device.device) }?;
+        assert!(!ctx.is_null());
+        unsafe { ffi_call!(cuDevicePrimaryCtxRelease, device.device) }?;
+        unsafe { device.reset_primary_context() }?;
+        let ctx = unsafe { ffi_new!(cuDevicePrimaryCtxRetain, device.device) }?;
+        assert!(!ctx.is_null());
+        unsafe { ffi_call!(cuDevicePrimaryCtxRelease, device.device) }?;
+        Ok(())
+    }
+
+    #[test]
+    fn nested_guard() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx1 = device.create_context();
+        let ctx2 = device.create_context();
+        let current = || unsafe { ffi_new!(cuCtxGetCurrent) };
+        let g1 = ctx1.guard()?;
+        assert_eq!(current()?, ctx1.ptr);
+        let g2 = ctx2.guard()?;
+        assert_eq!(current()?, ctx2.ptr);
+        drop(g2);
+        assert_eq!(current()?, ctx1.ptr);
+        drop(g1);
+        Ok(())
+    }
+
+    #[test]
+    fn reentrant_guard() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let current = || unsafe { ffi_new!(cuCtxGetCurrent) };
+        let g1 = ctx.guard()?;
+        let g2 = ctx.guard()?;
+        assert_eq!(current()?, ctx.ptr);
+        drop(g2);
+        assert_eq!(current()?, ctx.ptr);
+        drop(g1);
+        assert_ne!(current()?, ctx.ptr);
+        Ok(())
+    }
+
+    #[test]
+    fn context_pool() -> Result<()> {
+        let pool = ContextPool::new();
+        let current = || unsafe { ffi_new!(cuCtxGetCurrent) };
+        let task = || {
+            let device = Device::nth(0).unwrap();
+            let first = pool.with_device(&device, |ctx| {
+                assert_eq!(current().unwrap(), ctx.ptr);
+                ctx.ptr as usize
+            });
+            let second = pool.with_device(&device, |ctx| ctx.ptr as usize);
+            assert_eq!(first, second);
+            first
+        };
+        let (a, b) = thread::scope(|s| {
+            let a = s.spawn(task);
+            let b = s.spawn(task);
+            (a.join().unwrap(), b.join().unwrap())
+        });
+        assert_ne!(a, b);
+        assert_eq!(pool.len(), 2);
+        Ok(())
+    }
+
     #[test]
     fn create() -> Result<()> {
         let device = Device::nth(0)?;
@@ -289,6 +720,29 @@ mod tests {
         let _version = ctx_ref.version().unwrap(); // ctx has been expired
     }
 
+    #[cfg(debug_assertions)]
+    #[test]
+    fn expired_context_copy() -> Result<()> {
+        let device = Device::nth(0)?;
+        // Context created outside of accel, and wrapped without ownership
+        let ptr = unsafe {
+            ffi_new!(
+                cuCtxCreate_v2,
+                CUctx_flags_enum::CU_CTX_SCHED_AUTO as u32,
+                device.device
+            )
+        }?;
+        let ctx = unsafe { device.current_context() }?.unwrap();
+        let mut array = Array::<i32, Ix1>::zeros(&ctx, 12.into());
+        // The owner destroys the context while the array exists
+        unsafe { ffi_call!(cuCtxDestroy_v2, ptr) }?;
+        let result = array.try_copy_from(&[1_i32; 12][..]);
+        assert_eq!(result, Err(AccelError::InvalidContext));
+        // The array cannot be destroyed without its context
+        std::mem::forget(array);
+        Ok(())
+    }
+
     #[should_panic]
     #[test]
     fn expired_contexted_call() {
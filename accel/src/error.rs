@@ -1,15 +1,18 @@
 use cuda::cudaError_enum as DeviceError;
-use std::path::PathBuf;
+use std::{path::PathBuf, sync::RwLock};
 
 pub type Result<T> = ::std::result::Result<T, AccelError>;
 
 #[derive(thiserror::Error, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum AccelError {
     /// Raw errors originates from CUDA Device APIs
-    #[error("CUDA Device API Error: {api_name}, {error:?}")]
+    ///
+    /// `location` is the call site of the API in accel, e.g. `accel/src/device.rs:42`
+    #[error("CUDA Device API Error: {api_name}, {error:?} at {location}")]
     CUDAError {
         api_name: String,
         error: DeviceError,
+        location: &'static str,
     },
 
     // This is not an error potentially, but it should be a bug if not captured by accel
@@ -20,22 +23,155 @@ pub enum AccelError {
     #[error("Assertion in device code has failed")]
     DeviceAssertionFailed,
 
+    /// CUDA Driver API is used before `cuInit`
+    #[error("CUDA Driver API is not initialized. Call `accel::init()` before using it")]
+    NotInitialized,
+
     #[error("No device found for given ID")]
     DeviceNotFound { id: usize, count: usize },
 
+    /// Number of elements mismatch between shapes
+    #[error("Cannot reshape {from} elements into {to} elements")]
+    ShapeMismatch { from: usize, to: usize },
+
+    /// Number of elements mismatch between destination and source of copy
+    #[error("Destination has {dest} elements but source has {src} elements")]
+    SizeMismatch { dest: usize, src: usize },
+
+    /// Context referred by [ContextRef], or wrapped without ownership, has been destroyed
+    ///
+    /// This is checked only in debug build.
+    ///
+    /// [ContextRef]: ../device/struct.ContextRef.html
+    #[error("Context has been destroyed")]
+    InvalidContext,
+
+    /// Host memory has been already registered into CUDA memory system
+    ///
+    /// The existing registration can be used instead of registering again.
+    #[error("Host memory is already registered")]
+    AlreadyRegistered,
+
+    /// Argument rejected by accel before calling CUDA Driver API
+    #[error("Invalid value: {message}")]
+    InvalidValue { message: String },
+
     #[error("File not found: {path:?}")]
     FileNotFound { path: PathBuf },
+
+    /// Failure of file operations except for [AccelError::FileNotFound]
+    ///
+    /// [AccelError::FileNotFound]: #variant.FileNotFound
+    #[error("IO error on {path:?}: {kind:?}")]
+    IoError {
+        path: PathBuf,
+        kind: std::io::ErrorKind,
+    },
+}
+
+type ErrorHandler = Box<dyn Fn(&AccelError) + Send + Sync>;
+
+static ERROR_HANDLER: RwLock<Option<ErrorHandler>> = RwLock::new(None);
+
+/// Set a handler for errors which cannot be returned to the caller, e.g. failures in `Drop`
+///
+/// These errors are reported by `log::error!` by default.
+/// The handler replaces the previous one, and is shared by all threads.
+///
+/// ```
+/// accel::set_error_handler(|e| panic!("CUDA error in destructor: {}", e));
+/// # accel::reset_error_handler();
+/// ```
+pub fn set_error_handler(f: impl Fn(&AccelError) + Send + Sync + 'static) {
+    *ERROR_HANDLER.write().unwrap() = Some(Box::new(f));
+}
+
+/// Restore the default handler using `log::error!`
+pub fn reset_error_handler() {
+    *ERROR_HANDLER.write().unwrap() = None;
+}
+
+/// Set a handler like [set_error_handler] until the returned guard is dropped
+///
+/// The previous handler is restored when the guard is dropped.
+/// Guards must be dropped in the reverse order of creation to restore handlers correctly.
+///
+/// ```
+/// let _guard = accel::scoped_error_handler(|e| panic!("CUDA error in destructor: {}", e));
+/// ```
+///
+/// [set_error_handler]: ./fn.set_error_handler.html
+pub fn scoped_error_handler(f: impl Fn(&AccelError) + Send + Sync + 'static) -> ErrorHandlerGuard {
+    let previous = ERROR_HANDLER.write().unwrap().replace(Box::new(f));
+    ErrorHandlerGuard { previous }
+}
+
+/// Guard restoring the previous error handler, created by [scoped_error_handler]
+///
+/// [scoped_error_handler]: ./fn.scoped_error_handler.html
+#[must_use = "The previous handler is restored immediately"]
+pub struct ErrorHandlerGuard {
+    previous: Option<ErrorHandler>,
+}
+
+impl Drop for ErrorHandlerGuard {
+    fn drop(&mut self) {
+        *ERROR_HANDLER.write().unwrap() = self.previous.take();
+    }
+}
+
+/// Report an error which cannot be returned to the caller
+pub(crate) fn report_error(msg: &str, e: &AccelError) {
+    match ERROR_HANDLER.read().unwrap().as_ref() {
+        Some(handler) => handler(e),
+        None => log::error!("{}: {:?}", msg, e),
+    }
+}
+
+/// Install a logger capturing warnings, and get the captured messages
+///
+/// The logger is installed only once in the test process.
+/// Warnings of other tests running in parallel are also captured.
+#[cfg(test)]
+pub(crate) fn captured_warnings() -> std::sync::MutexGuard<'static, Vec<String>> {
+    use std::sync::{Mutex, Once};
+
+    static WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static INSTALL: Once = Once::new();
+    struct Capture;
+    impl log::Log for Capture {
+        fn enabled(&self, _: &log::Metadata) -> bool {
+            true
+        }
+        fn log(&self, record: &log::Record) {
+            if record.level() == log::Level::Warn {
+                WARNINGS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+        fn flush(&self) {}
+    }
+    static LOGGER: Capture = Capture;
+    INSTALL.call_once(|| {
+        log::set_logger(&LOGGER).unwrap();
+        log::set_max_level(log::LevelFilter::Warn);
+    });
+    WARNINGS.lock().unwrap()
 }
 
 /// Convert return code of CUDA Driver/Runtime API into Result
-pub(crate) fn check(error: DeviceError, api_name: &str) -> Result<()> {
+pub(crate) fn check(error: DeviceError, api_name: &str, location: &'static str) -> Result<()> {
     match error {
         DeviceError::CUDA_SUCCESS => Ok(()),
         DeviceError::CUDA_ERROR_ASSERT => Err(AccelError::DeviceAssertionFailed),
         DeviceError::CUDA_ERROR_NOT_READY => Err(AccelError::AsyncOperationNotReady),
+        DeviceError::CUDA_ERROR_NOT_INITIALIZED => Err(AccelError::NotInitialized),
+        DeviceError::CUDA_ERROR_HOST_MEMORY_ALREADY_REGISTERED => {
+            Err(AccelError::AlreadyRegistered)
+        }
         _ => Err(AccelError::CUDAError {
             api_name: api_name.into(),
             error,
+            location,
         }),
     }
 }
@@ -44,7 +180,11 @@ pub(crate) fn check(error: DeviceError, api_name: &str) -> Result<()> {
 macro_rules! ffi_call {
     ($ffi:path $(,$args:expr)*) => {
         {
-            $crate::error::check($ffi($($args),*), stringify!($ffi))
+            $crate::error::check(
+                $ffi($($args),*),
+                stringify!($ffi),
+                concat!(file!(), ":", line!()),
+            )
         }
     };
 }
@@ -54,7 +194,12 @@ macro_rules! ffi_new {
     ($ffi:path $(,$args:expr)*) => {
         {
             let mut value = ::std::mem::MaybeUninit::uninit();
-            $crate::error::check($ffi(value.as_mut_ptr(), $($args),*), stringify!($ffi)).map(|_| value.assume_init())
+            $crate::error::check(
+                $ffi(value.as_mut_ptr(), $($args),*),
+                stringify!($ffi),
+                concat!(file!(), ":", line!()),
+            )
+            .map(|_| value.assume_init())
         }
     };
 }
@@ -110,7 +110,12 @@
 extern crate cuda_driver_sys as cuda;
 
 pub use accel_derive::kernel;
+// Used in the code generated by `#[derive(Scalar)]`
+#[doc(hidden)]
+pub use num_traits;
 
+pub mod benchmark;
+pub mod debug;
 pub mod device;
 pub mod error;
 pub mod linker;
@@ -118,8 +123,11 @@ pub mod memory;
 pub mod module;
 pub mod profiler;
 pub mod stream;
+pub mod transfer;
+pub mod version;
 
 pub use device::*;
+pub use error::{reset_error_handler, scoped_error_handler, set_error_handler};
 pub use linker::*;
 pub use memory::*;
 pub use module::*;
@@ -187,7 +187,7 @@ pub struct Linker {
 impl Drop for Linker {
     fn drop(&mut self) {
         if let Err(e) = unsafe { contexted_call!(self, cuLinkDestroy, self.state) } {
-            log::error!("Failed to release Linker: {:?}", e)
+            report_error("Failed to release Linker", &e);
         }
     }
 }
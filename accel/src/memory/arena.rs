@@ -0,0 +1,249 @@
+//! Bump allocator on a single device memory
+
+use super::*;
+use crate::{error::Result, *};
+use cuda::*;
+use std::{
+    cell::Cell,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+/// Alignment of the head of each sub-allocation in bytes
+///
+/// This is the same as the alignment guaranteed by `cuMemAlloc`.
+const ARENA_ALIGNMENT: usize = 256;
+
+/// Device memory sub-allocated by a bump pointer
+///
+/// This allocates a single managed memory by [cuMemAllocManaged] in [Arena::new],
+/// and [Arena::alloc] only advances the pointer without calling CUDA memory allocation API.
+/// All sub-allocations are released at once by [Arena::reset].
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mut arena = Arena::new(&ctx, 4096);
+/// let mut a = arena.alloc::<f32>(12);
+/// let b = arena.alloc::<i32>(24);
+/// a[0] = 1.0;
+/// assert_eq!(b.len(), 24);
+/// drop((a, b));
+/// arena.reset();
+/// assert_eq!(arena.used(), 0);
+/// ```
+///
+/// [cuMemAllocManaged]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
+/// [Arena::new]: ./struct.Arena.html#method.new
+/// [Arena::alloc]: ./struct.Arena.html#method.alloc
+/// [Arena::reset]: ./struct.Arena.html#method.reset
+#[derive(Contexted)]
+pub struct Arena {
+    ptr: CUdeviceptr,
+    capacity: usize,
+    offset: Cell<usize>,
+    context: Context,
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        if let Err(e) = unsafe { contexted_call!(self, cuMemFree_v2, self.ptr) } {
+            error::report_error("Failed to free arena memory", &e);
+        }
+    }
+}
+
+impl Arena {
+    /// Allocate a new arena of `capacity` bytes
+    ///
+    /// Panic
+    /// -----
+    /// - if `capacity` is zero
+    pub fn new(context: &Context, capacity: usize) -> Self {
+        assert!(capacity > 0, "Zero-sized malloc is forbidden");
+        let ptr = unsafe {
+            contexted_new!(
+                context,
+                cuMemAllocManaged,
+                capacity,
+                CUmemAttach_flags_enum::CU_MEM_ATTACH_GLOBAL as u32
+            )
+        }
+        .expect("Cannot allocate arena memory");
+        Arena {
+            ptr,
+            capacity,
+            offset: Cell::new(0),
+            context: context.clone(),
+        }
+    }
+
+    /// Capacity of this arena in bytes
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Bytes used by sub-allocations including padding for alignment
+    pub fn used(&self) -> usize {
+        self.offset.get()
+    }
+
+    /// Sub-allocate a memory of `n` elements initialized by zero
+    ///
+    /// Panic
+    /// -----
+    /// - if the arena does not have enough space
+    pub fn alloc<T: Scalar>(&self, n: usize) -> ArenaSlice<'_, T> {
+        let head = self.offset.get().div_ceil(ARENA_ALIGNMENT) * ARENA_ALIGNMENT;
+        let bytes = n.checked_mul(T::size_of());
+        let end = bytes.and_then(|bytes| head.checked_add(bytes));
+        let (bytes, end) = match (bytes, end) {
+            (Some(bytes), Some(end)) if end <= self.capacity => (bytes, end),
+            _ => panic!("Arena does not have enough space"),
+        };
+        self.offset.set(end);
+        let ptr = self.ptr + head as CUdeviceptr;
+        unsafe { contexted_call!(self, cuMemsetD8_v2, ptr, 0, bytes) }
+            .expect("memset failed for arena memory");
+        ArenaSlice {
+            ptr: ptr as *mut T,
+            size: n,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Release all sub-allocations
+    ///
+    /// Since [ArenaSlice] borrows the arena, this can be called only after all of them are dropped.
+    pub fn reset(&mut self) {
+        self.offset.set(0);
+    }
+}
+
+/// Memory sub-allocated from [Arena]
+///
+/// This can be accessed from the host as a slice, and copied by [Memcpy] as [DeviceMemory].
+pub struct ArenaSlice<'arena, T> {
+    ptr: *mut T,
+    size: usize,
+    phantom: PhantomData<&'arena Arena>,
+}
+
+impl<T> Deref for ArenaSlice<'_, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.size) }
+    }
+}
+
+impl<T> DerefMut for ArenaSlice<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.size) }
+    }
+}
+
+impl<T: Scalar> Memory for ArenaSlice<'_, T> {
+    type Elem = T;
+    fn head_addr(&self) -> *const T {
+        self.ptr
+    }
+
+    fn head_addr_mut(&mut self) -> *mut T {
+        self.ptr
+    }
+
+    fn num_elem(&self) -> usize {
+        self.size
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Device
+    }
+}
+
+impl<T: Scalar> Continuous for ArenaSlice<'_, T> {
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alloc_reset() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut arena = Arena::new(&context, 4096);
+        {
+            let mut a = arena.alloc::<i32>(12);
+            let mut b = arena.alloc::<f32>(10);
+            let mut c = arena.alloc::<u8>(3);
+            assert_eq!(a.head_addr() as usize % ARENA_ALIGNMENT, 0);
+            assert_eq!(b.head_addr() as usize % ARENA_ALIGNMENT, 0);
+            assert_eq!(c.as_slice(), &[0; 3]);
+            a.iter_mut().for_each(|x| *x = 1);
+            b.iter_mut().for_each(|x| *x = 2.0);
+            c.iter_mut().for_each(|x| *x = 3);
+            assert_eq!(a.as_slice(), &[1; 12]);
+            assert_eq!(b.as_slice(), &[2.0; 10]);
+            assert_eq!(c.as_slice(), &[3; 3]);
+
+            let src = PageLockedMemory::<i32>::from_elem(&context, 12, 4);
+            a.as_mut_slice().copy_from(&src);
+            assert_eq!(a.as_slice(), &[4; 12]);
+        }
+        assert_eq!(arena.used(), 2 * ARENA_ALIGNMENT + 3);
+        arena.reset();
+        assert_eq!(arena.used(), 0);
+        let d = arena.alloc::<i32>(12);
+        assert_eq!(d.as_slice(), &[0; 12]);
+        Ok(())
+    }
+
+    #[test]
+    fn memcpy() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let arena = Arena::new(&context, 4096);
+        let mut a = arena.alloc::<i32>(12);
+        let src: Vec<i32> = (0..12).collect();
+        a.copy_from(&src);
+        assert_eq!(a.as_slice(), src.as_slice());
+
+        let mut host = PageLockedMemory::<i32>::zeros(&context, 12);
+        host.copy_from(&a);
+        assert_eq!(host.as_slice(), src.as_slice());
+
+        let mut b = arena.alloc::<i32>(12);
+        b.copy_from(&a);
+        let mut dest = vec![0; 12];
+        dest.copy_from(&b);
+        assert_eq!(dest, src);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Arena does not have enough space")]
+    #[test]
+    fn alloc_overflow() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let arena = Arena::new(&context, 16);
+        let _a = arena.alloc::<i32>(5);
+    }
+
+    #[should_panic(expected = "Arena does not have enough space")]
+    #[test]
+    fn alloc_overflow_wrapping() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let arena = Arena::new(&context, 16);
+        // `n * 4` wraps around to 0 without checking
+        let _a = arena.alloc::<i32>(usize::MAX / 4 + 1);
+    }
+}
@@ -4,7 +4,13 @@
 //! [Texture]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__TEXOBJECT.html#group__CUDA__TEXOBJECT
 //! [Surface]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__SURFOBJECT.html#group__CUDA__SURFOBJECT
 
-use crate::{contexted_call, contexted_new, device::Contexted, error::Result, *};
+use crate::{
+    contexted_call, contexted_new,
+    device::Contexted,
+    error::{AccelError, Result},
+    memory::{check_distinct, check_size},
+    *,
+};
 use cuda::*;
 use num_traits::ToPrimitive;
 use std::marker::PhantomData;
@@ -13,7 +19,7 @@ pub use cuda::CUDA_ARRAY3D_DESCRIPTOR as Descriptor;
 
 #[derive(Debug, Contexted)]
 pub struct Array<T, Dim> {
-    array: CUarray,
+    pub(crate) array: CUarray,
     dim: Dim,
     context: Context,
     phantom: PhantomData<T>,
@@ -22,16 +28,321 @@ pub struct Array<T, Dim> {
 impl<T, Dim> Drop for Array<T, Dim> {
     fn drop(&mut self) {
         if let Err(e) = unsafe { contexted_call!(self, cuArrayDestroy, self.array) } {
-            log::error!("Failed to cleanup array: {:?}", e);
+            error::report_error("Failed to cleanup array", &e);
         }
     }
 }
 
 impl<T: Scalar, Dim: Dimension> Array<T, Dim> {
+    /// Allocate a new array and copy a host slice in row-major order into it
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let array = Array::<u32, Ix2>::from_slice(&ctx, (2, 3).into(), &[0, 1, 2, 3, 4, 5]);
+    /// assert_eq!(array.to_vec(), vec![0, 1, 2, 3, 4, 5]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `src.len()` is not equal to `dim.len()`
+    pub fn from_slice(context: &Context, dim: Dim, src: &[T]) -> Self {
+        assert_eq!(
+            src.len(),
+            dim.len(),
+            "Slice length does not match array dimension"
+        );
+        let mut array = unsafe { Self::uninitialized(context, dim) };
+        array.copy_from(src);
+        array
+    }
+
+    /// Allocate a new array without initialization, returning an error for an invalid dimension
+    ///
+    /// An array with zero in any extent is rejected by [AccelError::InvalidValue]
+    /// since the error of `cuArray3DCreate` is opaque.
+    ///
+    /// Safety
+    /// ------
+    /// - Cause undefined behavior when read before write
+    ///
+    /// [AccelError::InvalidValue]: ../error/enum.AccelError.html#variant.InvalidValue
+    pub unsafe fn try_uninitialized(context: &Context, dim: Dim) -> Result<Self> {
+        if dim.len() == 0 {
+            return Err(AccelError::InvalidValue {
+                message: format!("Array has zero extent: {:?}", dim),
+            });
+        }
+        let desc = dim.as_descriptor::<T>();
+        debug_assert_eq!(
+            dim.len(),
+            desc.Width
+                * std::cmp::max(desc.Height, 1)
+                * std::cmp::max(desc.Depth, 1)
+                * (desc.NumChannels / T::channels()) as usize,
+            "Dimension::len is inconsistent with its descriptor"
+        );
+        let array = contexted_new!(context, cuArray3DCreate_v2, &desc)?;
+        Ok(Array {
+            array,
+            dim,
+            context: context.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Allocate a new array initialized by zero, returning an error for an invalid dimension
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// assert!(Array::<f32, Ix2>::try_zeros(&ctx, (0, 4).into()).is_err());
+    /// ```
+    pub fn try_zeros(context: &Context, dim: Dim) -> Result<Self> {
+        let mut array = unsafe { Self::try_uninitialized(context, dim) }?;
+        array.set(T::zero());
+        Ok(array)
+    }
+
     /// Get dimension
     pub fn dim(&self) -> &Dim {
         &self.dim
     }
+
+    /// Get `(width, height, depth)` of the array
+    ///
+    /// Missing dimensions are regarded as `1`, e.g. `depth` of a 2D array,
+    /// so that host code can iterate over arrays of any rank in the same manner.
+    /// The number of layers of a layered array is returned as `depth`.
+    /// `width` counts CUDA array elements, i.e. channels are not included.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let array = Array::<f32, Ix2>::zeros(&ctx, (4, 3).into());
+    /// assert_eq!(array.extents(), (4, 3, 1));
+    /// ```
+    pub fn extents(&self) -> (usize, usize, usize) {
+        (self.dim.width(), self.dim.height(), self.dim.depth())
+    }
+
+    /// Check if the array is layered, e.g. [Ix1Layered] or [Ix2Layered]
+    ///
+    /// This is derived from the flags of the descriptor of `Dim`,
+    /// so that generic code can branch without matching concrete dimension types.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let array = Array::<f32, Ix1Layered>::zeros(&ctx, (4, 3).into());
+    /// assert!(array.is_layered());
+    /// assert_eq!(array.num_layers(), 3);
+    /// ```
+    ///
+    /// [Ix1Layered]: ./struct.Ix1Layered.html
+    /// [Ix2Layered]: ./struct.Ix2Layered.html
+    pub fn is_layered(&self) -> bool {
+        self.flags().contains(ArrayFlag::LAYERED)
+    }
+
+    /// Number of layers of the array
+    ///
+    /// A non-layered array is regarded as a single layer, and returns `1`.
+    /// A layered cubemap counts each cubemap, which consists of six faces, as a layer.
+    pub fn num_layers(&self) -> usize {
+        let flags = self.flags();
+        if !flags.contains(ArrayFlag::LAYERED) {
+            return 1;
+        }
+        if flags.contains(ArrayFlag::CUBEMAP) {
+            self.dim.depth() / 6
+        } else {
+            self.dim.depth()
+        }
+    }
+
+    fn flags(&self) -> ArrayFlag {
+        ArrayFlag::from_bits_truncate(self.dim.as_descriptor::<T>().Flags)
+    }
+
+    /// Get descriptor of the array from CUDA driver
+    ///
+    /// This is useful to verify the format, number of channels, flags and extents
+    /// of the array, e.g. when the array is created outside of accel.
+    ///
+    /// See also [cuArray3DGetDescriptor].
+    ///
+    /// [cuArray3DGetDescriptor]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
+    pub fn descriptor(&self) -> Descriptor {
+        unsafe { contexted_new!(self, cuArray3DGetDescriptor_v2, self.array) }
+            .expect("Cannot get array descriptor")
+    }
+
+    /// Copy from another array of the same shape on the device
+    ///
+    /// The element type of `src` may differ from `T` if the byte sizes and the numbers of channels
+    /// are the same, e.g. `Array<u32, _>` into `Array<f32, _>`,
+    /// and the bytes are copied without conversion.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let src = Array::<u32, Ix1>::from_elem(&ctx, 4.into(), 1.0_f32.to_bits());
+    /// let mut dest = Array::<f32, Ix1>::zeros(&ctx, 4.into());
+    /// dest.copy_from_array(&src);
+    /// assert_eq!(dest.to_vec(), vec![1.0; 4]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the byte sizes of `T` and `U` are different
+    /// - if the numbers of channels of `T` and `U` are different
+    /// - if the shapes of arrays are different
+    pub fn copy_from_array<U: Scalar>(&mut self, src: &Array<U, Dim>) {
+        assert_eq!(
+            T::size_of(),
+            U::size_of(),
+            "Byte sizes of array elements mismatch"
+        );
+        assert_eq!(
+            T::channels(),
+            U::channels(),
+            "Numbers of channels of array elements mismatch"
+        );
+        assert_eq!(self.dim, src.dim, "Shapes of arrays mismatch");
+        let param = self.array_copy_param(src.array);
+        unsafe { contexted_call!(self, cuMemcpy3D_v2, &param) }
+            .expect("memcpy between arrays failed");
+    }
+
+    /// Copy from another array of the same shape asynchronously on `stream`
+    ///
+    /// Both arrays must not be modified or dropped until `stream` is synchronized.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// let src = Array::<f32, Ix2>::from_elem(&ctx, (4, 3).into(), 1.0);
+    /// let mut dest = Array::<f32, Ix2>::zeros(&ctx, (4, 3).into());
+    /// dest.copy_from_async(&src, &stream);
+    /// stream.sync().unwrap();
+    /// assert_eq!(dest.to_vec(), vec![1.0; 12]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the shapes of arrays are different
+    pub fn copy_from_async(&mut self, src: &Array<T, Dim>, stream: &Stream) {
+        assert_eq!(self.dim, src.dim, "Shapes of arrays mismatch");
+        let param = self.array_copy_param(src.array);
+        unsafe { contexted_call!(self, cuMemcpy3DAsync_v2, &param, stream.stream) }
+            .expect("Async memcpy between arrays failed");
+    }
+
+    /// Copy from another array of the same shape in the way specified by `mode`
+    ///
+    /// This dispatches to [Array::copy_from_array] for [CopyMode::Sync],
+    /// and to [Array::copy_from_async] for [CopyMode::Async].
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// let src = Array::<f32, Ix1>::from_elem(&ctx, 4.into(), 1.0);
+    /// let mut dest = Array::<f32, Ix1>::zeros(&ctx, 4.into());
+    /// dest.copy(&src, CopyMode::Async(&stream));
+    /// stream.sync().unwrap();
+    /// dest.copy(&src, CopyMode::Sync);
+    /// assert_eq!(dest.to_vec(), vec![1.0; 4]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the shapes of arrays are different
+    ///
+    /// [Array::copy_from_array]: ./struct.Array.html#method.copy_from_array
+    /// [Array::copy_from_async]: ./struct.Array.html#method.copy_from_async
+    /// [CopyMode::Sync]: ./enum.CopyMode.html#variant.Sync
+    /// [CopyMode::Async]: ./enum.CopyMode.html#variant.Async
+    pub fn copy(&mut self, src: &Array<T, Dim>, mode: CopyMode) {
+        match mode {
+            CopyMode::Sync => self.copy_from_array(src),
+            CopyMode::Async(stream) => self.copy_from_async(src, stream),
+        }
+    }
+
+    /// Parameter to copy the whole of `src` array of the same shape into this array
+    fn array_copy_param(&self, src: CUarray) -> CUDA_MEMCPY3D {
+        let dim = self.dim;
+        CUDA_MEMCPY3D {
+            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            srcArray: src,
+
+            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            dstArray: self.array,
+
+            WidthInBytes: dim.width() * T::size_of() * dim.num_channels().to_usize().unwrap(),
+            Height: dim.height(),
+            Depth: dim.depth(),
+
+            ..Default::default()
+        }
+    }
+
+    /// Copy into a newly allocated `Vec` in row-major order
+    ///
+    /// The array is copied directly into the uninitialized buffer of the `Vec`
+    /// without zero-initializing it before the copy.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let array = Array::<u32, Ix2>::from_elem(&ctx, (2, 3).into(), 1);
+    /// assert_eq!(array.to_vec(), vec![1; 6]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<T> {
+        let len = self.dim.len();
+        let mut vec = Vec::with_capacity(len);
+        // The copy writes exactly `len` elements from the head of the buffer,
+        // i.e. all elements are initialized when `set_len` is called.
+        // `T: Scalar` is `Copy`, and no destructor runs on the overwritten uninitialized memory.
+        unsafe {
+            self.copy_to_ptr(vec.as_mut_ptr())
+                .expect("memcpy from array failed");
+            vec.set_len(len);
+        }
+        vec
+    }
+
+    /// Copy whole of the array into the host or device memory starting at `dest`
+    ///
+    /// `dest` must be valid for writes of `self.dim.len()` elements.
+    unsafe fn copy_to_ptr(&self, dest: *mut T) -> Result<()> {
+        let dim = self.dim;
+        let param = CUDA_MEMCPY3D {
+            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            srcArray: self.array,
+
+            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_UNIFIED,
+            dstDevice: dest as CUdeviceptr,
+
+            WidthInBytes: dim.width() * T::size_of() * dim.num_channels().to_usize().unwrap(),
+            Height: dim.height(),
+            Depth: dim.depth(),
+
+            ..Default::default()
+        };
+        contexted_call!(self, cuMemcpy3D_v2, &param)
+    }
 }
 
 impl<T: Scalar, Dim: Dimension> Memory for Array<T, Dim> {
@@ -53,9 +364,9 @@ impl<T: Scalar, Dim: Dimension> Memory for Array<T, Dim> {
 }
 
 impl<T: Scalar, Dim: Dimension> Memcpy<[T]> for Array<T, Dim> {
-    fn copy_from(&mut self, src: &[T]) {
-        assert_ne!(self.head_addr(), src.head_addr());
-        assert_eq!(self.num_elem(), src.num_elem());
+    fn try_copy_from(&mut self, src: &[T]) -> Result<()> {
+        check_distinct(self.head_addr(), src.head_addr())?;
+        check_size(self.num_elem(), src.num_elem())?;
         let dim = self.dim;
         let param = CUDA_MEMCPY3D {
             srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_UNIFIED,
@@ -70,42 +381,30 @@ impl<T: Scalar, Dim: Dimension> Memcpy<[T]> for Array<T, Dim> {
 
             ..Default::default()
         };
-        unsafe { contexted_call!(self, cuMemcpy3D_v2, &param) }.expect("memcpy into array failed");
+        unsafe { contexted_call!(self, cuMemcpy3D_v2, &param) }
     }
 }
 
 impl<T: Scalar, Dim: Dimension> Memcpy<Array<T, Dim>> for [T] {
-    fn copy_from(&mut self, src: &Array<T, Dim>) {
-        assert_ne!(self.head_addr(), src.head_addr());
-        assert_eq!(self.num_elem(), src.num_elem());
-        let dim = src.dim;
-        let param = CUDA_MEMCPY3D {
-            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
-            srcArray: src.array,
-
-            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_UNIFIED,
-            dstDevice: self.as_mut_ptr() as CUdeviceptr,
-
-            WidthInBytes: dim.width() * T::size_of() * dim.num_channels().to_usize().unwrap(),
-            Height: dim.height(),
-            Depth: dim.depth(),
-
-            ..Default::default()
-        };
-        unsafe { contexted_call!(src, cuMemcpy3D_v2, &param) }.expect("memcpy from array failed");
+    fn try_copy_from(&mut self, src: &Array<T, Dim>) -> Result<()> {
+        check_distinct(self.head_addr(), src.head_addr())?;
+        check_size(self.num_elem(), src.num_elem())?;
+        // The context of the array is pushed in `copy_to_ptr`,
+        // since a host slice does not have any context
+        unsafe { src.copy_to_ptr(self.as_mut_ptr()) }
     }
 }
 
 macro_rules! impl_memcpy_array {
     ($t:path) => {
         impl<T: Scalar, Dim: Dimension> Memcpy<Array<T, Dim>> for $t {
-            fn copy_from(&mut self, src: &Array<T, Dim>) {
-                self.as_mut_slice().copy_from(src);
+            fn try_copy_from(&mut self, src: &Array<T, Dim>) -> Result<()> {
+                self.as_mut_slice().try_copy_from(src)
             }
         }
         impl<T: Scalar, Dim: Dimension> Memcpy<$t> for Array<T, Dim> {
-            fn copy_from(&mut self, src: &$t) {
-                self.copy_from(src.as_slice());
+            fn try_copy_from(&mut self, src: &$t) -> Result<()> {
+                self.try_copy_from(src.as_slice())
             }
         }
     };
@@ -114,6 +413,8 @@ macro_rules! impl_memcpy_array {
 impl_memcpy_array!(DeviceMemory::<T>);
 impl_memcpy_array!(PageLockedMemory::<T>);
 impl_memcpy_array!(RegisteredMemory::<'_, T>);
+impl_memcpy_array!(ArenaSlice::<'_, T>);
+impl_memcpy_array!(Vec::<T>);
 
 impl<T: Scalar, Dim: Dimension> Memset for Array<T, Dim> {
     fn set(&mut self, value: Self::Elem) {
@@ -123,18 +424,215 @@ impl<T: Scalar, Dim: Dimension> Memset for Array<T, Dim> {
     }
 }
 
+impl<T: Scalar, Dim: Dimension> Array<T, Dim> {
+    /// Set all elements to zero
+    ///
+    /// CUDA array is not a linear memory and cannot be cleared by `cuMemsetD8` directly.
+    /// Instead of staging zeros on the host as [Memset::set] does,
+    /// this copies zeros from a device memory into the array on the device.
+    ///
+    /// The device memory of zeros is allocated and cleared by `cuMemsetD8` in each call,
+    /// and freed before returning, i.e. the array does not keep additional device memory.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut array = Array::<f32, Ix2>::from_elem(&ctx, (4, 4).into(), 1.0);
+    /// array.zero();
+    /// assert_eq!(array.to_vec(), vec![0.0; 16]);
+    /// ```
+    pub fn zero(&mut self) {
+        let dim = self.dim;
+        let width_in_bytes = dim.width() * T::size_of() * dim.num_channels().to_usize().unwrap();
+        let bytes = dim.len() * T::size_of();
+        let zeros = unsafe { contexted_new!(self, cuMemAlloc_v2, bytes) }
+            .expect("Cannot allocate zeros for array");
+        let param = CUDA_MEMCPY3D {
+            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_DEVICE,
+            srcDevice: zeros,
+            srcPitch: width_in_bytes,
+            srcHeight: dim.height(),
+
+            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            dstArray: self.array,
+
+            WidthInBytes: width_in_bytes,
+            Height: dim.height(),
+            Depth: dim.depth(),
+
+            ..Default::default()
+        };
+        let result = unsafe {
+            contexted_call!(self, cuMemsetD8_v2, zeros, 0, bytes)
+                .and_then(|_| contexted_call!(self, cuMemcpy3D_v2, &param))
+        };
+        // Free zeros before panicking on the failure of clearing
+        if let Err(e) = unsafe { contexted_call!(self, cuMemFree_v2, zeros) } {
+            error::report_error("Failed to free zeros of array", &e);
+        }
+        result.expect("Failed to clear array by zeros");
+    }
+
+    /// Set all elements by `value` asynchronously on `stream`
+    ///
+    /// This stages `value` in a newly allocated page-locked host memory,
+    /// and issues an asynchronous copy into the array.
+    /// The returned guard keeps the staging memory and borrows the array until the stream completes.
+    /// Use [Array::set_async_with] to reuse the staging memory returned by [PinnedGuard::wait].
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut array = Array::<u32, Ix2>::zeros(&ctx, (4, 4).into());
+    /// let stream = Stream::new(&ctx);
+    /// let guard = array.set_async(3, &stream);
+    /// // do other works on host
+    /// guard.wait().unwrap();
+    /// ```
+    ///
+    /// [Array::set_async_with]: ./struct.Array.html#method.set_async_with
+    /// [PinnedGuard::wait]: ./struct.PinnedGuard.html#method.wait
+    pub fn set_async<'a>(&'a mut self, value: T, stream: &'a Stream) -> PinnedGuard<'a, T> {
+        let staging = unsafe { PageLockedMemory::uninitialized(&self.context, self.dim.len()) };
+        self.set_async_with(value, staging, stream)
+    }
+
+    /// [Array::set_async] using `staging` as the staging memory instead of allocating a new one
+    ///
+    /// This avoids allocating page-locked memory for each call, e.g. per-frame clears:
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut array = Array::<u32, Ix2>::zeros(&ctx, (4, 4).into());
+    /// let stream = Stream::new(&ctx);
+    /// let mut staging = PageLockedMemory::zeros(&ctx, 16);
+    /// for frame in 0..3 {
+    ///     staging = array.set_async_with(frame, staging, &stream).wait().unwrap();
+    /// }
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `staging` has fewer elements than the array
+    ///
+    /// [Array::set_async]: ./struct.Array.html#method.set_async
+    pub fn set_async_with<'a>(
+        &'a mut self,
+        value: T,
+        mut staging: PageLockedMemory<T>,
+        stream: &'a Stream,
+    ) -> PinnedGuard<'a, T> {
+        let len = self.dim.len();
+        assert!(
+            staging.len() >= len,
+            "Staging memory is smaller than the array"
+        );
+        staging[..len].iter_mut().for_each(|x| *x = value);
+        let dim = self.dim;
+        let param = CUDA_MEMCPY3D {
+            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_HOST,
+            srcHost: staging.as_ptr() as *const _,
+
+            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            dstArray: self.array,
+
+            WidthInBytes: dim.width() * T::size_of() * dim.num_channels().to_usize().unwrap(),
+            Height: dim.height(),
+            Depth: dim.depth(),
+
+            ..Default::default()
+        };
+        unsafe { contexted_call!(self, cuMemcpy3DAsync_v2, &param, stream.stream) }
+            .expect("Async memcpy into array failed");
+        PinnedGuard::new(staging, stream)
+    }
+
+    /// Set elements in a sub-region by `value` asynchronously on `stream`
+    ///
+    /// The region starts at `origin` and has the size `extent`,
+    /// both in `(width, height, depth)` of CUDA array elements as [Array::extents].
+    /// Only a tile of the region size is staged in page-locked host memory,
+    /// which is useful to clear a modified part of a large texture.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut array = Array::<u32, Ix2>::zeros(&ctx, (4, 4).into());
+    /// let stream = Stream::new(&ctx);
+    /// array.fill_region_async((1, 1, 0), (2, 2, 1), 3, &stream).wait().unwrap();
+    /// assert_eq!(array.to_vec()[5], 3);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `extent` is empty
+    /// - if the region is out of the array
+    ///
+    /// [Array::extents]: #method.extents
+    pub fn fill_region_async<'a>(
+        &'a mut self,
+        origin: (usize, usize, usize),
+        extent: (usize, usize, usize),
+        value: T,
+        stream: &'a Stream,
+    ) -> PinnedGuard<'a, T> {
+        let (x, y, z) = origin;
+        let (width, height, depth) = extent;
+        assert!(width * height * depth > 0, "Region is empty");
+        let (w, h, d) = self.extents();
+        assert!(
+            x + width <= w && y + height <= h && z + depth <= d,
+            "Region is out of the array"
+        );
+        let elem_size = T::size_of() * self.dim.num_channels().to_usize().unwrap();
+        let src = PageLockedMemory::from_elem(
+            &self.context,
+            width * height * depth * self.dim.num_channels().to_usize().unwrap(),
+            value,
+        );
+        let param = CUDA_MEMCPY3D {
+            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_HOST,
+            srcHost: src.as_ptr() as *const _,
+            srcPitch: width * elem_size,
+            srcHeight: height,
+
+            dstXInBytes: x * elem_size,
+            dstY: y,
+            dstZ: z,
+            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            dstArray: self.array,
+
+            WidthInBytes: width * elem_size,
+            Height: height,
+            Depth: depth,
+
+            ..Default::default()
+        };
+        unsafe { contexted_call!(self, cuMemcpy3DAsync_v2, &param, stream.stream) }
+            .expect("Async memcpy into array region failed");
+        PinnedGuard::new(src, stream)
+    }
+}
+
 impl<T: Scalar, Dim: Dimension> Allocatable for Array<T, Dim> {
     type Shape = Dim;
     unsafe fn uninitialized(context: &Context, dim: Dim) -> Self {
-        let desc = dim.as_descriptor::<T>();
-        let array =
-            contexted_new!(context, cuArray3DCreate_v2, &desc).expect("Cannot create a new array");
-        Array {
-            array,
-            dim,
-            context: context.clone(),
-            phantom: PhantomData,
-        }
+        Self::try_uninitialized(context, dim).expect("Cannot create a new array")
+    }
+}
+
+impl<T: Scalar, Dim: Dimension> AllocateLike for Array<T, Dim> {
+    fn shape(&self) -> Self::Shape {
+        self.dim
+    }
+
+    fn context(&self) -> &Context {
+        &self.context
     }
 }
 
@@ -184,6 +682,300 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn layers() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let array: Array<f32, Ix2Layered> = Array::zeros(&context, (10, 12, 8).into());
+        assert!(array.is_layered());
+        assert_eq!(array.num_layers(), 8);
+        let array: Array<f32, IxCubeLayered> = Array::zeros(&context, (8, 2).into());
+        assert!(array.is_layered());
+        assert_eq!(array.num_layers(), 2);
+        let array: Array<f32, Ix3> = Array::zeros(&context, (10, 12, 8).into());
+        assert!(!array.is_layered());
+        assert_eq!(array.num_layers(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn new_cube() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let array: Array<f32, IxCube> = Array::zeros(&context, 8.into());
+        let desc = array.descriptor();
+        assert_eq!(
+            ArrayFlag::from_bits_truncate(desc.Flags),
+            ArrayFlag::CUBEMAP
+        );
+        assert_eq!(desc.Width, 8);
+        assert_eq!(desc.Height, 8);
+        assert_eq!(desc.Depth, 6);
+        Ok(())
+    }
+
+    #[test]
+    fn new_cube_layered() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let array: Array<f32, IxCubeLayered> = Array::zeros(&context, (8, 2).into());
+        let desc = array.descriptor();
+        assert_eq!(
+            ArrayFlag::from_bits_truncate(desc.Flags),
+            ArrayFlag::CUBEMAP | ArrayFlag::LAYERED
+        );
+        assert_eq!(desc.Depth, 12);
+        Ok(())
+    }
+
+    #[test]
+    fn descriptor() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let dim: Ix2 = (10, 12).into();
+        let array: Array<f32, Ix2> = Array::zeros(&context, dim);
+        let desc = array.descriptor();
+        assert_eq!(desc.Width, dim.width);
+        assert_eq!(desc.Height, dim.height);
+        assert_eq!(desc.Depth, 0);
+        assert_eq!(desc.NumChannels, 1);
+        assert_eq!(desc.Format, f32::format());
+        assert_eq!(desc, dim.as_descriptor::<f32>());
+        Ok(())
+    }
+
+    #[test]
+    fn descriptor_integer_formats() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let dim: Ix2 = (10, 12).into();
+
+        let array = Array::<u8, Ix2>::zeros(&context, dim);
+        let desc = array.descriptor();
+        assert_eq!(desc.Format, ArrayFormatTag::CU_AD_FORMAT_UNSIGNED_INT8);
+        assert_eq!(desc.NumChannels, 1);
+
+        let array = Array::<i16, Ix2>::zeros(&context, dim);
+        let desc = array.descriptor();
+        assert_eq!(desc.Format, ArrayFormatTag::CU_AD_FORMAT_SIGNED_INT16);
+        assert_eq!(desc.NumChannels, 1);
+
+        assert_eq!(i8::format(), ArrayFormatTag::CU_AD_FORMAT_SIGNED_INT8);
+        assert_eq!(u16::format(), ArrayFormatTag::CU_AD_FORMAT_UNSIGNED_INT16);
+        assert_eq!(i32::format(), ArrayFormatTag::CU_AD_FORMAT_SIGNED_INT32);
+        assert_eq!(u32::format(), ArrayFormatTag::CU_AD_FORMAT_UNSIGNED_INT32);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_with_other_context_current() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut array = Array::<i32, Ix2>::zeros(&context, (4, 3).into());
+        let other = device.create_context();
+        let _g = other.guard()?;
+        let current = unsafe { ffi_new!(cuCtxGetCurrent) }?;
+        assert_ne!(ContextRef::from_ptr(current), *context);
+
+        let src: Vec<i32> = (0..12).collect();
+        array.copy_from(src.as_slice());
+        let mut dest = vec![0; 12];
+        dest.copy_from(&array);
+        assert_eq!(dest, src);
+        Ok(())
+    }
+
+    #[test]
+    fn dyn_dim() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let dim = DynDim::from_shape(&[3, 4])?;
+        let src: Vec<f32> = (0..12).map(|i| i as f32).collect();
+        let array = Array::<f32, DynDim>::from_slice(&context, dim, &src);
+        assert_eq!(array.extents(), (3, 4, 1));
+        assert_eq!(array.to_vec(), src);
+        Ok(())
+    }
+
+    #[test]
+    fn fill_region_async() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let mut array = Array::<u32, Ix2>::zeros(&context, (4, 4).into());
+        array
+            .fill_region_async((1, 2, 0), (2, 2, 1), 7, &stream)
+            .wait()?;
+        let v = array.to_vec();
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if (1..3).contains(&x) && (2..4).contains(&y) {
+                    7
+                } else {
+                    0
+                };
+                assert_eq!(v[y * 4 + x], expected);
+            }
+        }
+        Ok(())
+    }
+
+    #[should_panic(expected = "Region is out of the array")]
+    #[test]
+    fn fill_region_async_out_of_bounds() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let mut array = Array::<u32, Ix2>::zeros(&context, (4, 4).into());
+        let _guard = array.fill_region_async((3, 0, 0), (2, 2, 1), 7, &stream);
+    }
+
+    #[test]
+    fn extents() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let array = Array::<f32, Ix1>::zeros(&context, 8.into());
+        assert_eq!(array.extents(), (8, 1, 1));
+        let array = Array::<f32, Ix2>::zeros(&context, (8, 4).into());
+        assert_eq!(array.extents(), (8, 4, 1));
+        let array = Array::<f32, Ix3>::zeros(&context, (8, 4, 2).into());
+        assert_eq!(array.extents(), (8, 4, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn from_slice_2d() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let src: Vec<f32> = (0..10 * 12).map(|i| i as f32).collect();
+        let array = Array::<f32, Ix2>::from_slice(&context, (10, 12).into(), &src);
+        assert_eq!(array.to_vec(), src);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Slice length does not match array dimension")]
+    #[test]
+    fn from_slice_mismatch() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let _array = Array::<f32, Ix2>::from_slice(&context, (10, 12).into(), &[0.0; 10]);
+    }
+
+    #[test]
+    fn zero_extent() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let result = Array::<f32, Ix2>::try_zeros(&context, (0, 4).into());
+        assert!(matches!(result, Err(AccelError::InvalidValue { .. })));
+        let result = Array::<f32, Ix3>::try_zeros(&context, (4, 4, 0).into());
+        assert!(matches!(result, Err(AccelError::InvalidValue { .. })));
+        assert!(Array::<f32, Ix2>::try_zeros(&context, (1, 4).into()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn copy_from_async() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let dim: Ix3 = (8, 4, 2).into();
+        let src: Vec<u32> = (0..dim.len() as u32).collect();
+        let a = Array::<u32, Ix3>::from_slice(&context, dim, &src);
+        let mut b = Array::<u32, Ix3>::zeros(&context, dim);
+        b.copy_from_async(&a, &stream);
+        stream.sync()?;
+        assert_eq!(b.to_vec(), src);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_mode() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let dim: Ix2 = (8, 4).into();
+        let a = Array::<u32, Ix2>::from_elem(&context, dim, 1);
+        let b = Array::<u32, Ix2>::from_elem(&context, dim, 2);
+        let mut dest = Array::<u32, Ix2>::zeros(&context, dim);
+        dest.copy(&a, CopyMode::Sync);
+        assert_eq!(dest.to_vec(), vec![1; dim.len()]);
+        dest.copy(&b, CopyMode::Async(&stream));
+        stream.sync()?;
+        assert_eq!(dest.to_vec(), vec![2; dim.len()]);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Shapes of arrays mismatch")]
+    #[test]
+    fn copy_from_async_mismatch() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let a = Array::<u32, Ix2>::zeros(&context, (4, 3).into());
+        let mut b = Array::<u32, Ix2>::zeros(&context, (3, 4).into());
+        b.copy_from_async(&a, &stream);
+    }
+
+    #[test]
+    fn vector_scalar() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut array = Array::<Float4, Ix1>::zeros(&context, 8.into());
+        let desc = array.descriptor();
+        assert_eq!(desc.NumChannels, 4);
+        assert_eq!(desc.Format, f32::format());
+
+        let src: Vec<Float4> = (0..8)
+            .map(|i| Float4::new(i as f32, 1.0, 2.0, 3.0))
+            .collect();
+        array.copy_from(&src);
+        assert_eq!(array.to_vec(), src);
+        Ok(())
+    }
+
+    #[test]
+    fn set_async() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let n = 3;
+        let m = 4;
+        let mut array = Array::<u32, Ix2>::zeros(&context, (n, m).into());
+        let stream = Stream::new(&context);
+        let guard = array.set_async(3, &stream);
+        let _staging = guard.wait()?;
+        let mut dst = PageLockedMemory::zeros(&context, n * m);
+        dst.copy_from(&array);
+        for i in 0..n * m {
+            assert_eq!(dst[i], 3_u32);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn set_async_with() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut array = Array::<u32, Ix2>::zeros(&context, (3, 4).into());
+        let stream = Stream::new(&context);
+        let staging = array.set_async(3, &stream).wait()?;
+        let head = staging.as_ptr();
+        let staging = array.set_async_with(5, staging, &stream).wait()?;
+        assert_eq!(staging.as_ptr(), head);
+        assert_eq!(array.to_vec(), vec![5; 12]);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Staging memory is smaller than the array")]
+    #[test]
+    fn set_async_with_small_staging() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let mut array = Array::<u32, Ix2>::zeros(&context, (3, 4).into());
+        let stream = Stream::new(&context);
+        let staging = PageLockedMemory::zeros(&context, 11);
+        let _guard = array.set_async_with(5, staging, &stream);
+    }
+
     #[test]
     fn memcpy_h2a2h_1d() -> Result<()> {
         let device = Device::nth(0)?;
@@ -201,6 +993,87 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn memcpy_vec2a2vec_1d() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let n = 10;
+        let src: Vec<u32> = (0..n as u32).collect();
+        let mut dst = vec![0_u32; n];
+        let mut array = unsafe { Array::<u32, Ix1>::uninitialized(&context, n.into()) };
+        array.copy_from(&src);
+        dst.copy_from(&array);
+        assert_eq!(dst, src);
+        Ok(())
+    }
+
+    #[test]
+    fn to_vec_2d() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let src = vec![1_u32, 2, 3, 4];
+        let mut array = unsafe { Array::<u32, Ix2>::uninitialized(&context, (2, 2).into()) };
+        array.copy_from(&src);
+        assert_eq!(array.to_vec(), src);
+        Ok(())
+    }
+
+    #[test]
+    fn to_vec_3d() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let dim: Ix3 = (32, 16, 8).into();
+        let src: Vec<f32> = (0..dim.len()).map(|i| i as f32).collect();
+        let array = Array::<f32, Ix3>::from_slice(&context, dim, &src);
+        let vec = array.to_vec();
+        assert_eq!(vec.len(), dim.len());
+        assert_eq!(vec, src);
+        Ok(())
+    }
+
+    #[test]
+    fn zero() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut array = Array::<u32, Ix2>::from_elem(&context, (3, 4).into(), 5);
+        assert_eq!(array.to_vec(), vec![5; 12]);
+        array.zero();
+        assert_eq!(array.to_vec(), vec![0; 12]);
+
+        array.set(7);
+        array.zero();
+        assert_eq!(array.to_vec(), vec![0; 12]);
+
+        let mut layered = Array::<u32, Ix2Layered>::from_elem(&context, (3, 4, 2).into(), 5);
+        layered.zero();
+        assert_eq!(layered.to_vec(), vec![0; 24]);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_from_array_bits() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let src_data: Vec<u32> = (0..12).map(|i| (i as f32 * 0.5).to_bits()).collect();
+        let mut src = Array::<u32, Ix2>::zeros(&context, (3, 4).into());
+        src.copy_from(&src_data);
+        let mut dest = Array::<f32, Ix2>::zeros(&context, (3, 4).into());
+        dest.copy_from_array(&src);
+        let bits: Vec<u32> = dest.to_vec().iter().map(|x| x.to_bits()).collect();
+        assert_eq!(bits, src_data);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Byte sizes of array elements mismatch")]
+    #[test]
+    fn copy_from_array_size_mismatch() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let src = Array::<u16, Ix1>::zeros(&context, 4.into());
+        let mut dest = Array::<f32, Ix1>::zeros(&context, 4.into());
+        dest.copy_from_array(&src);
+    }
+
     #[test]
     fn memcpy_d2a2d_2d() -> Result<()> {
         let device = Device::nth(0)?;
@@ -3,14 +3,25 @@
 use super::*;
 use crate::{error::*, *};
 use cuda::*;
+use num_traits::Float;
 use std::{
+    fs::OpenOptions,
     marker::PhantomData,
-    ops::{Deref, DerefMut},
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut, Range},
+    path::Path,
+    sync::Arc,
 };
 
 use cuda::CUmemAttach_flags_enum as AttachFlag;
 
 /// Memory allocated on the device.
+///
+/// This is allocated as managed memory by [cuMemAllocManaged],
+/// i.e. it can be accessed from the host as a slice,
+/// and copies from/to it are executed by the CUDA driver using its context.
+///
+/// [cuMemAllocManaged]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
 #[derive(Contexted)]
 pub struct DeviceMemory<T> {
     ptr: CUdeviceptr,
@@ -22,7 +33,7 @@ pub struct DeviceMemory<T> {
 impl<T> Drop for DeviceMemory<T> {
     fn drop(&mut self) {
         if let Err(e) = unsafe { contexted_call!(self, cuMemFree_v2, self.ptr) } {
-            log::error!("Failed to free device memory: {:?}", e);
+            report_error("Failed to free device memory", &e);
         }
     }
 }
@@ -40,6 +51,678 @@ impl<T> DerefMut for DeviceMemory<T> {
     }
 }
 
+/// Handle of a memory leaked by [DeviceMemory::leak]
+///
+/// This keeps the context of the memory, and is consumed by [DeviceMemory::free_leaked].
+/// Dropping this handle leaks the memory and its context permanently.
+///
+/// [DeviceMemory::leak]: ./struct.DeviceMemory.html#method.leak
+/// [DeviceMemory::free_leaked]: ./struct.DeviceMemory.html#method.free_leaked
+#[derive(Debug)]
+pub struct LeakedMemory {
+    ptr: CUdeviceptr,
+    context: *const ContextOwned,
+}
+
+unsafe impl Send for LeakedMemory {}
+unsafe impl Sync for LeakedMemory {}
+
+impl<T: Scalar> DeviceMemory<T> {
+    /// Copy into a newly allocated page-locked host memory in the same context
+    ///
+    /// This is more efficient than `to_vec` if the result will be transferred again.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = DeviceMemory::<i32>::from_elem(&ctx, 12, 3);
+    /// let host = mem.to_page_locked();
+    /// assert_eq!(host.as_slice(), &[3; 12]);
+    /// ```
+    pub fn to_page_locked(&self) -> PageLockedMemory<T> {
+        let mut host = unsafe { PageLockedMemory::uninitialized(&self.context, self.size) };
+        host.copy_from(self);
+        host
+    }
+
+    /// Allocate a new device memory and copy `src` into it asynchronously on `stream`
+    ///
+    /// `src` is copied into page-locked staging memory first, and then transferred to the device.
+    /// The device memory is handed out by [PendingUpload::wait] after the transfer completes,
+    /// since reading it during the transfer is a data race.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// let src = vec![1_i32; 12];
+    /// let upload = DeviceMemory::upload_async(&ctx, &src, &stream);
+    /// // do other works on host
+    /// let mem = upload.wait().unwrap();
+    /// assert_eq!(mem.as_slice(), src.as_slice());
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `src` is empty
+    ///
+    /// [PendingUpload::wait]: ./struct.PendingUpload.html#method.wait
+    pub fn upload_async<'stream>(
+        context: &Context,
+        src: &[T],
+        stream: &'stream Stream,
+    ) -> PendingUpload<'stream, T> {
+        let mut staging = unsafe { PageLockedMemory::uninitialized(context, src.len()) };
+        staging.as_mut_slice().copy_from_slice(src);
+        let mem = unsafe { Self::uninitialized(context, src.len()) };
+        unsafe {
+            contexted_call!(
+                context,
+                cuMemcpyHtoDAsync_v2,
+                mem.ptr,
+                staging.as_ptr() as *const _,
+                src.len() * T::size_of(),
+                stream.stream
+            )
+        }
+        .expect("Async memcpy into device memory failed");
+        PendingUpload {
+            staging: PinnedGuard::new(staging, stream),
+            mem,
+        }
+    }
+
+    /// Copy into a newly allocated page-locked host memory asynchronously on `stream`
+    ///
+    /// An event is recorded on `stream` just after the copy,
+    /// and the host memory is handed out by [PendingDownload::wait] after the event completes,
+    /// since reading it during the transfer is a data race.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// let mem = DeviceMemory::<i32>::from_elem(&ctx, 12, 3);
+    /// let download = mem.download_async(&stream);
+    /// // do other works on host
+    /// let host = download.wait().unwrap();
+    /// assert_eq!(host.as_slice(), &[3; 12]);
+    /// ```
+    ///
+    /// [PendingDownload::wait]: ./struct.PendingDownload.html#method.wait
+    pub fn download_async(&self, stream: &Stream) -> PendingDownload<'_, T> {
+        let mut host = unsafe { PageLockedMemory::uninitialized(&self.context, self.size) };
+        unsafe {
+            contexted_call!(
+                self,
+                cuMemcpyDtoHAsync_v2,
+                host.as_mut_ptr() as *mut _,
+                self.ptr,
+                self.size * T::size_of(),
+                stream.stream
+            )
+        }
+        .expect("Async memcpy from device memory failed");
+        let event = Event::new(self.context.clone());
+        unsafe { contexted_call!(self, cuEventRecord, event.event, stream.stream) }
+            .expect("Failed to set event record");
+        PendingDownload {
+            host: Some(host),
+            event,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Copy each of `segments` into the region starting at the corresponding offset
+    ///
+    /// Elements not covered by any segment, e.g. padding between packed sequences, are not touched.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut mem = DeviceMemory::<i32>::zeros(&ctx, 8);
+    /// mem.copy_from_ragged(&[&[1, 2], &[3]], &[0, 4]);
+    /// assert_eq!(mem.as_slice(), &[1, 2, 0, 0, 3, 0, 0, 0]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the numbers of `segments` and `offsets` mismatch
+    /// - if a segment exceeds the memory
+    /// - if segments overlap
+    pub fn copy_from_ragged(&mut self, segments: &[&[T]], offsets: &[usize]) {
+        assert_eq!(
+            segments.len(),
+            offsets.len(),
+            "Numbers of segments and offsets mismatch"
+        );
+        let mut ranges: Vec<(usize, usize)> = segments
+            .iter()
+            .zip(offsets)
+            .map(|(seg, &offset)| (offset, offset + seg.len()))
+            .collect();
+        ranges.sort_unstable();
+        for (i, &(_, end)) in ranges.iter().enumerate() {
+            assert!(end <= self.size, "Segment is out of range");
+            if let Some(&(next, _)) = ranges.get(i + 1) {
+                assert!(end <= next, "Segments overlap");
+            }
+        }
+        for (seg, &offset) in segments.iter().zip(offsets) {
+            if !seg.is_empty() {
+                self[offset..offset + seg.len()].copy_from(*seg);
+            }
+        }
+    }
+
+    /// Write the content of this memory into the file at `path` through a memory map
+    ///
+    /// The file is created or truncated to the size of this memory, and mapped into the host memory.
+    /// The mapped region is registered as page-locked memory during the copy from the device,
+    /// or copied as pageable memory if the registration fails.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = DeviceMemory::<u8>::from_elem(&ctx, 12, 7);
+    /// let path = std::env::temp_dir().join("accel-dump-to-mmap-doc.bin");
+    /// mem.dump_to_mmap(&path).unwrap();
+    /// assert_eq!(std::fs::read(&path).unwrap(), vec![7; 12]);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(unix)]
+    pub fn dump_to_mmap(&self, path: impl AsRef<Path>) -> Result<()> {
+        use std::os::unix::io::AsRawFd;
+        let path = path.as_ref();
+        let io_error = |e: std::io::Error| AccelError::IoError {
+            path: path.to_owned(),
+            kind: e.kind(),
+        };
+        let bytes = self.size * T::size_of();
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .map_err(io_error)?;
+        file.set_len(bytes as u64).map_err(io_error)?;
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                bytes,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err(io_error(std::io::Error::last_os_error()));
+        }
+        let registered =
+            unsafe { contexted_call!(self, cuMemHostRegister_v2, addr, bytes, 0) }.is_ok();
+        let result = unsafe { contexted_call!(self, cuMemcpyDtoH_v2, addr, self.ptr, bytes) };
+        if registered {
+            if let Err(e) = unsafe { contexted_call!(self, cuMemHostUnregister, addr) } {
+                report_error("Failed to unregister memory", &e);
+            }
+        }
+        if unsafe { libc::munmap(addr, bytes) } != 0 {
+            return Err(io_error(std::io::Error::last_os_error()));
+        }
+        result
+    }
+
+    /// Fill the memory by repeating an arbitrary byte `pattern`
+    ///
+    /// CUDA driver only has `cuMemsetD{8,16,32}` for 8/16/32-bit patterns.
+    /// This is useful to fill a pattern spanning several elements, and
+    /// [Memset::set] also uses this for larger scalars, e.g. [Float4].
+    /// This builds one tile of `pattern` in a page-locked buffer, uploads it to the head of the memory,
+    /// and then fills the rest by copying the already filled region on the device with doubling sizes.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut mem = DeviceMemory::<u32>::zeros(&ctx, 12);
+    /// mem.memset_pattern(&0x0123_4567_89ab_cdef_u64.to_le_bytes());
+    /// assert_eq!(&mem[..4], &[0x89ab_cdef, 0x0123_4567, 0x89ab_cdef, 0x0123_4567]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `pattern` is empty
+    /// - if the size of memory in bytes is not a multiple of `pattern.len()`
+    pub fn memset_pattern(&mut self, pattern: &[u8]) {
+        assert!(!pattern.is_empty(), "Empty pattern is forbidden");
+        let total = self.size * T::size_of();
+        assert_eq!(
+            total % pattern.len(),
+            0,
+            "Memory size is not a multiple of pattern length"
+        );
+        let mut tile =
+            unsafe { PageLockedMemory::<u8>::uninitialized(&self.context, pattern.len()) };
+        tile.as_mut_slice().copy_from_slice(pattern);
+        unsafe {
+            contexted_call!(
+                self,
+                cuMemcpyHtoD_v2,
+                self.ptr,
+                tile.as_ptr() as *const _,
+                pattern.len()
+            )
+        }
+        .expect("memcpy of pattern tile failed");
+        let mut filled = pattern.len();
+        while filled < total {
+            let n = filled.min(total - filled);
+            unsafe {
+                contexted_call!(
+                    self,
+                    cuMemcpyDtoD_v2,
+                    self.ptr + filled as CUdeviceptr,
+                    self.ptr,
+                    n
+                )
+            }
+            .expect("memcpy for tiling pattern failed");
+            filled += n;
+        }
+    }
+
+    /// Divide into two mutable views at `mid`, as [slice::split_at_mut]
+    ///
+    /// The first view contains `[0, mid)` and the second one contains `[mid, len)`.
+    /// Since they do not overlap, each of them can be a target of a copy or kernel
+    /// on different streams independently.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// let (mut a, mut b) = mem.split_at_mut(4);
+    /// a.iter_mut().for_each(|x| *x = 1);
+    /// b.iter_mut().for_each(|x| *x = 2);
+    /// assert_eq!(&mem[..4], &[1; 4]);
+    /// assert_eq!(&mem[4..], &[2; 8]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `mid > len`
+    ///
+    /// [slice::split_at_mut]: https://doc.rust-lang.org/std/primitive.slice.html#method.split_at_mut
+    pub fn split_at_mut(&mut self, mid: usize) -> (DeviceSliceMut<'_, T>, DeviceSliceMut<'_, T>) {
+        assert!(mid <= self.size, "Split point is out of bounds");
+        let head = DeviceSliceMut {
+            ptr: self.ptr as *mut T,
+            size: mid,
+            phantom: PhantomData,
+        };
+        let tail = DeviceSliceMut {
+            ptr: unsafe { (self.ptr as *mut T).add(mid) },
+            size: self.size - mid,
+            phantom: PhantomData,
+        };
+        (head, tail)
+    }
+
+    /// Ordinal of the device where this memory exists, see [device_ordinal]
+    ///
+    /// [device_ordinal]: ./fn.device_ordinal.html
+    pub fn device_ordinal(&self) -> Result<i32> {
+        device_ordinal(self.head_addr())
+    }
+
+    /// Attach this memory to `stream` by [cuStreamAttachMemAsync]
+    ///
+    /// Attached memory is associated only with `stream` instead of all streams.
+    /// On devices without concurrent managed access, e.g. pre-Pascal GPUs,
+    /// the host may access it while other streams are running kernels.
+    /// This is unnecessary for that purpose if [Device::concurrent_managed_access] is true.
+    /// The attachment takes effect after preceding tasks of `stream` complete,
+    /// and the host must not access the memory while `stream` has unfinished tasks.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// mem.attach_stream(&stream).unwrap();
+    /// stream.sync().unwrap();
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the memory is not managed memory
+    ///
+    /// [cuStreamAttachMemAsync]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__STREAM.html
+    /// [Device::concurrent_managed_access]: ../device/struct.Device.html#method.concurrent_managed_access
+    pub fn attach_stream(&self, stream: &Stream) -> Result<()> {
+        assert!(is_managed(self.head_addr()), "Memory is not managed");
+        unsafe {
+            contexted_call!(
+                self,
+                cuStreamAttachMemAsync,
+                stream.stream,
+                self.ptr,
+                0, // must be zero for managed memory, i.e. the whole allocation
+                AttachFlag::CU_MEM_ATTACH_SINGLE as u32
+            )
+        }?;
+        Ok(())
+    }
+
+    /// Set `CU_POINTER_ATTRIBUTE_SYNC_MEMOPS` of this memory by [cuPointerSetAttribute]
+    ///
+    /// If enabled, synchronous memory operations initiated on this memory, e.g. `cuMemcpy`,
+    /// are always synchronized with other operations on it.
+    /// Disabling it allows them to overlap, which is useful to tune advanced pipelines.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// mem.set_sync_memops(true).unwrap();
+    /// ```
+    ///
+    /// [cuPointerSetAttribute]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__UNIFIED.html
+    pub fn set_sync_memops(&self, enabled: bool) -> Result<()> {
+        let value: u32 = enabled.into();
+        unsafe {
+            contexted_call!(
+                self,
+                cuPointerSetAttribute,
+                &value as *const u32 as *const c_void,
+                CUpointer_attribute::CU_POINTER_ATTRIBUTE_SYNC_MEMOPS,
+                self.ptr
+            )
+        }?;
+        Ok(())
+    }
+
+    /// Advise the driver that `device` accesses this memory by [cuMemAdvise]
+    ///
+    /// If `enabled`, this sets `CU_MEM_ADVISE_SET_ACCESSED_BY`, and the memory is kept mapped
+    /// in the page tables of `device` to avoid page faults when it is accessed from `device`.
+    /// Otherwise `CU_MEM_ADVISE_UNSET_ACCESSED_BY` removes the advice.
+    /// This is useful for managed memory shared by multiple GPUs.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// if device.concurrent_managed_access().unwrap() {
+    ///     mem.set_accessed_by(&device, true).unwrap();
+    /// }
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the memory is not managed memory
+    ///
+    /// [cuMemAdvise]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__UNIFIED.html
+    pub fn set_accessed_by(&self, device: &Device, enabled: bool) -> Result<()> {
+        assert!(is_managed(self.head_addr()), "Memory is not managed");
+        let advice = if enabled {
+            CUmem_advise::CU_MEM_ADVISE_SET_ACCESSED_BY
+        } else {
+            CUmem_advise::CU_MEM_ADVISE_UNSET_ACCESSED_BY
+        };
+        unsafe {
+            contexted_call!(
+                self,
+                cuMemAdvise,
+                self.ptr,
+                self.size * T::size_of(),
+                advice,
+                device.device
+            )
+        }?;
+        Ok(())
+    }
+
+    /// Leak the memory to make it live until the end of the program
+    ///
+    /// This is useful for allocations which must be valid for the whole program,
+    /// e.g. memories captured into a CUDA graph.
+    /// The context of this memory is also kept alive.
+    /// The leaked memory can be released manually by [DeviceMemory::free_leaked]
+    /// using the returned [LeakedMemory] handle.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let (mem, _handle) = DeviceMemory::from_elem(&ctx, 12, 3).leak();
+    /// let mem: &'static mut [i32] = mem;
+    /// assert_eq!(mem, &[3; 12]);
+    /// ```
+    ///
+    /// [DeviceMemory::free_leaked]: #method.free_leaked
+    /// [LeakedMemory]: ./struct.LeakedMemory.html
+    pub fn leak(self) -> (&'static mut [T], LeakedMemory) {
+        let mem = ManuallyDrop::new(self);
+        // The context is not dropped either, so the memory remains valid
+        let context = unsafe { std::ptr::read(&mem.context) };
+        let handle = LeakedMemory {
+            ptr: mem.ptr,
+            context: Arc::into_raw(context),
+        };
+        let slice = unsafe { std::slice::from_raw_parts_mut(mem.ptr as *mut T, mem.size) };
+        (slice, handle)
+    }
+
+    /// Release a memory leaked by [DeviceMemory::leak]
+    ///
+    /// The reference to the context kept by [DeviceMemory::leak] is also released.
+    ///
+    /// Safety
+    /// ------
+    /// - The slice returned with `leaked` by [DeviceMemory::leak] must not be used after this call
+    ///
+    /// [DeviceMemory::leak]: #method.leak
+    pub unsafe fn free_leaked(leaked: LeakedMemory) -> Result<()> {
+        let context = Arc::from_raw(leaked.context);
+        contexted_call!(&context, cuMemFree_v2, leaked.ptr)
+    }
+
+    /// Allocate a new memory in `context` and copy `parts` into it in order
+    ///
+    /// Each part is copied on the device by `cuMemcpy`.
+    /// All parts must belong to `context`, since a copy between contexts on different devices
+    /// requires peer access to be enabled.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let a = DeviceMemory::<i32>::from_elem(&ctx, 2, 1);
+    /// let b = DeviceMemory::<i32>::from_elem(&ctx, 3, 2);
+    /// let c = DeviceMemory::concat(&ctx, &[&a, &b]);
+    /// assert_eq!(c.as_slice(), &[1, 1, 2, 2, 2]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the total length is zero
+    /// - if any part belongs to a context other than `context`
+    pub fn concat(context: &Context, parts: &[&DeviceMemory<T>]) -> Self {
+        for (i, part) in parts.iter().enumerate() {
+            assert!(
+                *part.context == **context,
+                "Part {} of concat belongs to another context",
+                i
+            );
+        }
+        let len = parts.iter().map(|part| part.len()).sum();
+        let mut mem = unsafe { Self::uninitialized(context, len) };
+        let mut offset = 0;
+        for part in parts {
+            mem[offset..offset + part.len()].copy_from(part.as_slice());
+            offset += part.len();
+        }
+        mem
+    }
+
+    /// Copy elements in `range` into a host vector for inspection
+    ///
+    /// This is intended for debugging, e.g. poking at device state in a debugger,
+    /// and is slow since every call issues a synchronous copy. Use [Memcpy] for data transfer.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// mem.fill_iota(0, 1);
+    /// assert_eq!(mem.peek(3..5).unwrap(), vec![3, 4]);
+    /// assert!(mem.peek(10..13).is_err());
+    /// ```
+    ///
+    /// Returns [AccelError::InvalidValue] if `range` is out of bounds.
+    ///
+    /// [Memcpy]: ./trait.Memcpy.html
+    /// [AccelError::InvalidValue]: ../error/enum.AccelError.html#variant.InvalidValue
+    pub fn peek(&self, range: Range<usize>) -> Result<Vec<T>> {
+        let src = self
+            .get(range.clone())
+            .ok_or_else(|| AccelError::InvalidValue {
+                message: format!(
+                    "Range {:?} is out of bounds for {} elements",
+                    range, self.size
+                ),
+            })?;
+        let mut dest = vec![T::zero(); src.len()];
+        if !dest.is_empty() {
+            dest.as_mut_slice().try_copy_from(src)?;
+        }
+        Ok(dest)
+    }
+
+    /// Fill with the sequence `start, start + step, start + 2 * step, ...`
+    ///
+    /// The sequence is generated in a page-locked staging memory, and uploaded into this memory.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut mem = DeviceMemory::<f32>::zeros(&ctx, 4);
+    /// mem.fill_iota(1.0, 0.5);
+    /// assert_eq!(mem.as_slice(), &[1.0, 1.5, 2.0, 2.5]);
+    /// ```
+    pub fn fill_iota(&mut self, start: T, step: T) {
+        let mut staging = unsafe { PageLockedMemory::<T>::uninitialized(&self.context, self.size) };
+        let mut value = start;
+        for v in staging.iter_mut() {
+            *v = value;
+            value = value + step;
+        }
+        self.copy_from(&staging);
+    }
+
+    /// Free many memories at once
+    ///
+    /// Dropping each memory pushes and pops its context around `cuMemFree`.
+    /// This frees consecutive memories in the same context under a single push,
+    /// which reduces the overhead for many small memories, e.g. on teardown of a pool.
+    /// All memories are freed even if some of them fail, and the first error is returned.
+    ///
+    /// Stream-ordered free by `cuMemFreeAsync` is not used since it requires CUDA 11.2.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let buffers: Vec<_> = (0..4).map(|_| DeviceMemory::<f32>::zeros(&ctx, 12)).collect();
+    /// DeviceMemory::free_batch(buffers).unwrap();
+    /// ```
+    pub fn free_batch(buffers: Vec<Self>) -> Result<()> {
+        let mut result = Ok(());
+        // The guard is declared before the context to pop it before the context is dropped
+        let mut current: Option<(ContextGuard, Context)> = None;
+        for mem in buffers {
+            let same_context = matches!(&current, Some((_, ctx)) if Arc::ptr_eq(ctx, &mem.context));
+            if !same_context {
+                // Pop the previous context before pushing the next one
+                drop(current.take());
+                current = Some((mem.context.guard()?, mem.context.clone()));
+            }
+            let mut mem = ManuallyDrop::new(mem);
+            if let Err(e) = unsafe { ffi_call!(cuMemFree_v2, mem.ptr) } {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+            // Drop the context without calling `Drop` of the memory itself
+            unsafe { std::ptr::drop_in_place(&mut mem.context) };
+        }
+        result
+    }
+
+    /// Compare with host data, and returns the first differing index on mismatch
+    ///
+    /// If lengths differ, the length of the shorter one is returned
+    /// unless a mismatch is found before it.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = DeviceMemory::<i32>::from_elem(&ctx, 4, 1);
+    /// assert_eq!(mem.eq_host(&[1, 1, 1, 1]), Ok(()));
+    /// assert_eq!(mem.eq_host(&[1, 1, 2, 1]), Err(2));
+    /// ```
+    pub fn eq_host(&self, other: &[T]) -> std::result::Result<(), usize> {
+        first_mismatch(self, other, |a, b| a == b)
+    }
+}
+
+impl<T: Scalar + Float> DeviceMemory<T> {
+    /// Compare with `other` elementwise within the absolute tolerance `tol`,
+    /// and returns the first differing index on mismatch
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let a = DeviceMemory::<f32>::from_elem(&ctx, 4, 1.0);
+    /// let b = DeviceMemory::<f32>::from_elem(&ctx, 4, 1.0 + 1e-6);
+    /// assert_eq!(a.approx_eq(&b, 1e-5), Ok(()));
+    /// assert_eq!(a.approx_eq(&b, 1e-7), Err(0));
+    /// ```
+    pub fn approx_eq(&self, other: &[T], tol: T) -> std::result::Result<(), usize> {
+        first_mismatch(self, other, |a, b| (a - b).abs() <= tol)
+    }
+}
+
+fn first_mismatch<T: Copy>(
+    a: &[T],
+    b: &[T],
+    eq: impl Fn(T, T) -> bool,
+) -> std::result::Result<(), usize> {
+    if let Some(i) = a.iter().zip(b).position(|(&a, &b)| !eq(a, b)) {
+        return Err(i);
+    }
+    if a.len() != b.len() {
+        return Err(a.len().min(b.len()));
+    }
+    Ok(())
+}
+
 impl<T: Scalar> Memory for DeviceMemory<T> {
     type Elem = T;
     fn head_addr(&self) -> *const T {
@@ -91,9 +774,12 @@ impl<T: Scalar> Memset for DeviceMemory<T> {
                     self.num_elem()
                 )
             }
-            .expect("memset failed for 64-bit scalar"),
+            .expect("memset failed for 32-bit scalar"),
             _ => {
-                unimplemented!("memset for Device memory is only supported for 8/16/32-bit scalars")
+                let pattern = unsafe {
+                    std::slice::from_raw_parts(&value as *const T as *const u8, T::size_of())
+                };
+                self.memset_pattern(pattern)
             }
         }
     }
@@ -108,6 +794,112 @@ impl<T: Scalar> Continuous for DeviceMemory<T> {
     }
 }
 
+/// Mutable view of a part of [DeviceMemory], created by [DeviceMemory::split_at_mut]
+///
+/// [DeviceMemory::split_at_mut]: ./struct.DeviceMemory.html#method.split_at_mut
+pub struct DeviceSliceMut<'a, T> {
+    ptr: *mut T,
+    size: usize,
+    phantom: PhantomData<&'a mut DeviceMemory<T>>,
+}
+
+impl<T> Deref for DeviceSliceMut<'_, T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.size) }
+    }
+}
+
+impl<T> DerefMut for DeviceSliceMut<'_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.size) }
+    }
+}
+
+impl<T: Scalar> Memory for DeviceSliceMut<'_, T> {
+    type Elem = T;
+    fn head_addr(&self) -> *const T {
+        self.ptr
+    }
+
+    fn head_addr_mut(&mut self) -> *mut T {
+        self.ptr
+    }
+
+    fn num_elem(&self) -> usize {
+        self.size
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Device
+    }
+}
+
+impl<T: Scalar> Continuous for DeviceSliceMut<'_, T> {
+    fn as_slice(&self) -> &[T] {
+        self
+    }
+    fn as_mut_slice(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+/// Device memory being uploaded by [DeviceMemory::upload_async]
+///
+/// `Drop` waits for the stream before freeing the device and staging memories.
+///
+/// [DeviceMemory::upload_async]: ./struct.DeviceMemory.html#method.upload_async
+#[must_use = "The upload is waited and the device memory is freed immediately"]
+pub struct PendingUpload<'stream, T: Scalar> {
+    // Declared first to wait for the stream before `mem` is freed
+    staging: PinnedGuard<'stream, T>,
+    mem: DeviceMemory<T>,
+}
+
+impl<T: Scalar> PendingUpload<'_, T> {
+    /// Wait until the upload completes, and get the device memory
+    pub fn wait(self) -> Result<DeviceMemory<T>> {
+        self.staging.wait()?;
+        Ok(self.mem)
+    }
+}
+
+/// Host memory being downloaded by [DeviceMemory::download_async]
+///
+/// This borrows the source device memory until the download completes,
+/// and `Drop` waits for the download before freeing the host memory.
+///
+/// [DeviceMemory::download_async]: ./struct.DeviceMemory.html#method.download_async
+#[must_use = "The download is waited and the host memory is freed immediately"]
+pub struct PendingDownload<'src, T: Scalar> {
+    host: Option<PageLockedMemory<T>>,
+    event: Event,
+    phantom: PhantomData<&'src DeviceMemory<T>>,
+}
+
+impl<T: Scalar> Drop for PendingDownload<'_, T> {
+    fn drop(&mut self) {
+        if self.host.is_some() {
+            if let Err(e) = self.event.sync() {
+                report_error("Failed to sync download before freeing host memory", &e);
+            }
+        }
+    }
+}
+
+impl<T: Scalar> PendingDownload<'_, T> {
+    /// Check if the download has completed
+    pub fn query(&self) -> bool {
+        self.event.query()
+    }
+
+    /// Wait until the download completes, and get the host memory
+    pub fn wait(mut self) -> Result<PageLockedMemory<T>> {
+        self.event.sync()?;
+        Ok(self.host.take().unwrap())
+    }
+}
+
 impl<T: Scalar> Allocatable for DeviceMemory<T> {
     type Shape = usize;
     unsafe fn uninitialized(context: &Context, size: usize) -> Self {
@@ -128,6 +920,16 @@ impl<T: Scalar> Allocatable for DeviceMemory<T> {
     }
 }
 
+impl<T: Scalar> AllocateLike for DeviceMemory<T> {
+    fn shape(&self) -> Self::Shape {
+        self.size
+    }
+
+    fn context(&self) -> &Context {
+        &self.context
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,6 +945,331 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn upload_async() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let src: Vec<i32> = (0..12).collect();
+        let mem = DeviceMemory::upload_async(&context, &src, &stream).wait()?;
+        assert_eq!(mem.to_vec(), src);
+        Ok(())
+    }
+
+    #[test]
+    fn split_at_mut() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        {
+            let (mut a, mut b) = mem.split_at_mut(5);
+            assert_eq!(a.len(), 5);
+            assert_eq!(b.len(), 7);
+            let src = PageLockedMemory::<i32>::from_elem(&context, 5, 1);
+            a.as_mut_slice().copy_from(&src);
+            b.iter_mut().for_each(|x| *x = 2);
+        }
+        assert_eq!(&mem[..5], &[1; 5]);
+        assert_eq!(&mem[5..], &[2; 7]);
+        let (a, b) = mem.split_at_mut(12);
+        assert_eq!(a.len(), 12);
+        assert!(b.is_empty());
+        Ok(())
+    }
+
+    #[should_panic(expected = "Split point is out of bounds")]
+    #[test]
+    fn split_at_mut_out_of_bounds() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        let _ = mem.split_at_mut(13);
+    }
+
+    #[test]
+    fn download_async() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let src: Vec<i32> = (0..12).collect();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        mem.copy_from(src.as_slice());
+        let host = mem.download_async(&stream).wait()?;
+        assert_eq!(host.as_slice(), src.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn copy_from_ragged() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::from_elem(&context, 12, -1);
+        mem.copy_from_ragged(&[&[1, 2, 3], &[4, 5]], &[0, 8]);
+        assert_eq!(mem.as_slice(), &[1, 2, 3, -1, -1, -1, -1, -1, 4, 5, -1, -1]);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Segments overlap")]
+    #[test]
+    fn copy_from_ragged_overlap() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        mem.copy_from_ragged(&[&[4, 5], &[1, 2, 3]], &[8, 6]);
+    }
+
+    #[should_panic(expected = "Segment is out of range")]
+    #[test]
+    fn copy_from_ragged_out_of_range() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        mem.copy_from_ragged(&[&[1, 2, 3]], &[10]);
+    }
+
+    #[test]
+    fn set_sync_memops() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mem = DeviceMemory::<i32>::zeros(&context, 12);
+        for &enabled in &[true, false, true] {
+            mem.set_sync_memops(enabled)?;
+            let mut value: u32 = 0;
+            unsafe {
+                ffi_call!(
+                    cuPointerGetAttribute,
+                    &mut value as *mut u32 as *mut c_void,
+                    CUpointer_attribute::CU_POINTER_ATTRIBUTE_SYNC_MEMOPS,
+                    mem.ptr
+                )
+            }?;
+            assert_eq!(value != 0, enabled);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn concat() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut a = DeviceMemory::<u32>::zeros(&context, 3);
+        a.fill_iota(0, 1);
+        let mut b = DeviceMemory::<u32>::zeros(&context, 4);
+        b.fill_iota(3, 1);
+        let mut c = DeviceMemory::<u32>::zeros(&context, 5);
+        c.fill_iota(7, 1);
+        let joined = DeviceMemory::concat(&context, &[&a, &b, &c]);
+        assert_eq!(joined.to_vec(), (0..12).collect::<Vec<u32>>());
+        Ok(())
+    }
+
+    #[should_panic(expected = "Part 1 of concat belongs to another context")]
+    #[test]
+    fn concat_other_context() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let other = device.create_context();
+        let a = DeviceMemory::<u32>::zeros(&context, 3);
+        let b = DeviceMemory::<u32>::zeros(&other, 4);
+        let _joined = DeviceMemory::concat(&context, &[&a, &b]);
+    }
+
+    #[test]
+    fn peek() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<u32>::zeros(&context, 10);
+        mem.fill_iota(0, 1);
+        assert_eq!(mem.peek(5..7)?, vec![5, 6]);
+        assert_eq!(mem.peek(3..3)?, vec![]);
+        assert!(matches!(
+            mem.peek(8..11),
+            Err(AccelError::InvalidValue { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn fill_iota() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<u32>::zeros(&context, 10);
+        mem.fill_iota(0, 1);
+        assert_eq!(mem.to_vec(), (0..10).collect::<Vec<u32>>());
+        Ok(())
+    }
+
+    #[test]
+    fn free_batch() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let buffers: Vec<_> = (0..100)
+            .map(|i| DeviceMemory::<i32>::from_elem(&context, 16, i))
+            .collect();
+        DeviceMemory::free_batch(buffers)?;
+        assert_eq!(Arc::strong_count(&context), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn eq_host() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mem = DeviceMemory::<i32>::from_elem(&context, 12, 3);
+        assert_eq!(mem.eq_host(&[3; 12]), Ok(()));
+        let mut host = vec![3; 12];
+        host[5] = 4;
+        assert_eq!(mem.eq_host(&host), Err(5));
+        assert_eq!(mem.eq_host(&[3; 10]), Err(10));
+        Ok(())
+    }
+
+    #[test]
+    fn approx_eq() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let a = DeviceMemory::<f32>::from_elem(&context, 12, 1.0);
+        let mut b = DeviceMemory::<f32>::from_elem(&context, 12, 1.0 + 1e-6);
+        assert_eq!(a.approx_eq(&b, 1e-4), Ok(()));
+        b[7] = 1.1;
+        assert_eq!(a.approx_eq(&b, 1e-4), Err(7));
+        Ok(())
+    }
+
+    #[test]
+    fn leak() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let count = Arc::strong_count(&context);
+        let (mem, handle) = DeviceMemory::<i32>::from_elem(&context, 12, 3).leak();
+        assert_eq!(Arc::strong_count(&context), count + 1);
+        mem[0] = 4;
+        assert_eq!(mem[..2], [4, 3]);
+        unsafe { DeviceMemory::<i32>::free_leaked(handle) }?;
+        assert_eq!(Arc::strong_count(&context), count);
+        Ok(())
+    }
+
+    #[test]
+    fn attach_stream() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let stream = Stream::new(&context);
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        mem.attach_stream(&stream)?;
+        stream.sync()?;
+        mem[0] = 1;
+        assert_eq!(mem[..2], [1, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn set_accessed_by() -> Result<()> {
+        let device = Device::nth(0)?;
+        if !device.concurrent_managed_access()? {
+            return Ok(());
+        }
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        mem.set_accessed_by(&device, true)?;
+        mem[0] = 1;
+        assert_eq!(mem[..2], [1, 0]);
+        mem.set_accessed_by(&device, false)?;
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dump_to_mmap() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let src: Vec<i32> = (0..1024).collect();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, src.len());
+        mem.copy_from(src.as_slice());
+        let path =
+            std::env::temp_dir().join(format!("accel-dump-to-mmap-{}.bin", std::process::id()));
+        mem.dump_to_mmap(&path)?;
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let expected: Vec<u8> = src.iter().flat_map(|x| x.to_le_bytes().to_vec()).collect();
+        assert_eq!(bytes, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn memset_pattern() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<u32>::zeros(&context, 26);
+        mem.memset_pattern(&[0xde, 0xad, 0xbe, 0xef]);
+        assert!(mem.iter().all(|&x| x == 0xefbe_adde));
+
+        // 64-bit pattern spanning two elements
+        let pattern = 0x0011_2233_4455_6677_u64;
+        mem.memset_pattern(&pattern.to_le_bytes());
+        for pair in mem.chunks(2) {
+            assert_eq!(pair, &[0x4455_6677, 0x0011_2233]);
+        }
+        Ok(())
+    }
+
+    #[should_panic(expected = "Memory size is not a multiple of pattern length")]
+    #[test]
+    fn memset_pattern_mismatch() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<u32>::zeros(&context, 3);
+        mem.memset_pattern(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn vector_scalar() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<Float4>::zeros(&context, 12);
+        assert_eq!(mem.to_vec(), vec![Float4::zero(); 12]);
+        mem.set(Float4::new(1.0, 2.0, 3.0, 4.0));
+        assert_eq!(mem.to_vec(), vec![Float4::new(1.0, 2.0, 3.0, 4.0); 12]);
+
+        let src: Vec<Float4> = (0..12).map(|i| Float4::splat(i as f32)).collect();
+        let mut dev = DeviceMemory::<Float4>::zeros(&context, 12);
+        dev.copy_from(&src);
+        let mut dst = vec![Float4::zero(); 12];
+        dst.copy_from(&dev);
+        assert_eq!(dst, src);
+        Ok(())
+    }
+
+    #[test]
+    fn device_ordinal() -> Result<()> {
+        if Device::get_count()? < 2 {
+            return Ok(());
+        }
+        let ctx0 = Device::nth(0)?.create_context();
+        let ctx1 = Device::nth(1)?.create_context();
+        let mem0 = DeviceMemory::<i32>::zeros(&ctx0, 12);
+        let mem1 = DeviceMemory::<i32>::zeros(&ctx1, 12);
+        assert_eq!(mem0.device_ordinal()?, 0);
+        assert_eq!(mem1.device_ordinal()?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn to_page_locked() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&context, 12);
+        for (i, v) in mem.iter_mut().enumerate() {
+            *v = i as i32;
+        }
+        let host = mem.to_page_locked();
+        assert_eq!(host.memory_type(), MemoryType::PageLocked);
+        for i in 0..12 {
+            assert_eq!(host[i], i as i32);
+        }
+        Ok(())
+    }
+
     #[should_panic(expected = "Zero-sized malloc is forbidden")]
     #[test]
     fn device_new_zero() {
@@ -1,4 +1,4 @@
-use crate::*;
+use crate::{error::*, *};
 use derive_new::new;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{ToPrimitive, Zero};
@@ -14,6 +14,8 @@ pub use cuda::CUDA_ARRAY3D_DESCRIPTOR as Descriptor;
 ///   the size of "CUDA array element" is 64bit as packed two 32bit float values.
 /// - We call `T` element, although "CUDA array element" represents `[T; num_channels]`.
 ///   `Memory::num_elem()` returns how many `T` exists in this array.
+/// - Vector types, e.g. `T=Float4`, have their own channels given by `Scalar::channels()`,
+///   and the descriptor has `num_channels * T::channels()` channels.
 ///
 /// [RGBA color model]: https://en.wikipedia.org/wiki/RGBA_color_model
 #[repr(u32)]
@@ -33,10 +35,33 @@ impl Default for NumChannels {
     }
 }
 
+/// Number of channels of the descriptor, i.e. `num_channels * T::channels()`
+///
+/// Panic
+/// -----
+/// - if the number is not 1, 2 or 4, which CUDA arrays do not support,
+///   e.g. `NumChannels::Two` of [Float4](./struct.Float4.html)
+fn descriptor_channels<T: Scalar>(num_channels: NumChannels) -> u32 {
+    let channels = num_channels.to_u32().unwrap() * T::channels();
+    assert!(
+        matches!(channels, 1 | 2 | 4),
+        "CUDA array supports 1, 2 or 4 channels, but {:?} of {}-channel scalar results in {} channels",
+        num_channels,
+        T::channels(),
+        channels
+    );
+    channels
+}
+
 pub trait Dimension: Zero + Debug + Clone + Copy + PartialEq {
     fn as_descriptor<T: Scalar>(&self) -> Descriptor;
 
     /// Number of elements
+    ///
+    /// This must be equal to `width * height * depth * num_channels`
+    /// where `height` and `depth` are regarded as `1` if they are zero in the descriptor,
+    /// and `num_channels` of the descriptor is divided by `T::channels()`.
+    /// Staging buffers for the array are allocated using this value.
     fn len(&self) -> usize;
 
     /// Get number of element `T` in each "CUDA Array element"
@@ -53,6 +78,24 @@ pub trait Dimension: Zero + Debug + Clone + Copy + PartialEq {
     fn depth(&self) -> usize {
         std::cmp::max(self.as_descriptor::<u32>().Depth, 1)
     }
+
+    /// Check `new` has the same number of elements, and returns it
+    ///
+    /// ```
+    /// # use accel::*;
+    /// let dim = Ix2::new(3, 4);
+    /// assert_eq!(dim.reshape(Ix1::new(12)).unwrap(), Ix1::new(12));
+    /// assert!(dim.reshape(Ix2::new(5, 2)).is_err());
+    /// ```
+    fn reshape<D: Dimension>(&self, new: D) -> Result<D> {
+        if self.len() != new.len() {
+            return Err(AccelError::ShapeMismatch {
+                from: self.len(),
+                to: new.len(),
+            });
+        }
+        Ok(new)
+    }
 }
 
 /// Spec of 1D Array
@@ -108,7 +151,7 @@ impl Dimension for Ix1 {
             Width: self.width,
             Height: 0,
             Depth: 0,
-            NumChannels: self.num_channels.to_u32().unwrap(),
+            NumChannels: descriptor_channels::<T>(self.num_channels),
             Flags: ArrayFlag::empty().bits(),
             Format: T::format(),
         }
@@ -154,6 +197,17 @@ impl Add for Ix2 {
     }
 }
 
+impl Ix2 {
+    /// Regard rows as layers of 1D arrays
+    pub fn into_ix1_layered(self) -> Ix1Layered {
+        Ix1Layered {
+            width: self.width,
+            depth: self.height,
+            num_channels: self.num_channels,
+        }
+    }
+}
+
 impl Zero for Ix2 {
     fn zero() -> Self {
         Ix2::new(0, 0)
@@ -170,7 +224,7 @@ impl Dimension for Ix2 {
             Width: self.width,
             Height: self.height,
             Depth: 0,
-            NumChannels: self.num_channels.to_u32().unwrap(),
+            NumChannels: descriptor_channels::<T>(self.num_channels),
             Flags: ArrayFlag::empty().bits(),
             Format: T::format(),
         }
@@ -219,6 +273,18 @@ impl Add for Ix3 {
     }
 }
 
+impl Ix3 {
+    /// Regard depth as layers of 2D arrays
+    pub fn into_ix2_layered(self) -> Ix2Layered {
+        Ix2Layered {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            num_channels: self.num_channels,
+        }
+    }
+}
+
 impl Zero for Ix3 {
     fn zero() -> Self {
         Ix3::new(0, 0, 0)
@@ -235,7 +301,7 @@ impl Dimension for Ix3 {
             Width: self.width,
             Height: self.height,
             Depth: self.depth,
-            NumChannels: self.num_channels.to_u32().unwrap(),
+            NumChannels: descriptor_channels::<T>(self.num_channels),
             Flags: ArrayFlag::empty().bits(),
             Format: T::format(),
         }
@@ -283,6 +349,17 @@ impl Add for Ix1Layered {
     }
 }
 
+impl Ix1Layered {
+    /// Regard layers as rows of a 2D array
+    pub fn into_ix2(self) -> Ix2 {
+        Ix2 {
+            width: self.width,
+            height: self.depth,
+            num_channels: self.num_channels,
+        }
+    }
+}
+
 impl Zero for Ix1Layered {
     fn zero() -> Self {
         Self::new(0, 0)
@@ -299,7 +376,7 @@ impl Dimension for Ix1Layered {
             Width: self.width,
             Height: 0,
             Depth: self.depth,
-            NumChannels: self.num_channels.to_u32().unwrap(),
+            NumChannels: descriptor_channels::<T>(self.num_channels),
             Flags: ArrayFlag::LAYERED.bits(),
             Format: T::format(),
         }
@@ -351,6 +428,18 @@ impl Add for Ix2Layered {
     }
 }
 
+impl Ix2Layered {
+    /// Regard layers as depth of a 3D array
+    pub fn into_ix3(self) -> Ix3 {
+        Ix3 {
+            width: self.width,
+            height: self.height,
+            depth: self.depth,
+            num_channels: self.num_channels,
+        }
+    }
+}
+
 impl Zero for Ix2Layered {
     fn zero() -> Self {
         Self::new(0, 0, 0)
@@ -367,7 +456,7 @@ impl Dimension for Ix2Layered {
             Width: self.width,
             Height: self.height,
             Depth: self.depth,
-            NumChannels: self.num_channels.to_u32().unwrap(),
+            NumChannels: descriptor_channels::<T>(self.num_channels),
             Flags: ArrayFlag::LAYERED.bits(),
             Format: T::format(),
         }
@@ -382,6 +471,342 @@ impl Dimension for Ix2Layered {
     }
 }
 
+/// Spec of Cubemap Array
+///
+/// A cubemap array consists of six square 2D faces,
+/// i.e. its height is equal to the width and its depth is six.
+#[derive(Debug, Clone, Copy, PartialEq, new)]
+pub struct IxCube {
+    /// Width (and height) of each face
+    pub width: usize,
+    #[new(default)]
+    pub num_channels: NumChannels,
+}
+
+impl From<usize> for IxCube {
+    fn from(width: usize) -> IxCube {
+        IxCube {
+            width,
+            num_channels: NumChannels::One,
+        }
+    }
+}
+
+impl Add for IxCube {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        assert_eq!(self.num_channels, other.num_channels);
+        Self {
+            width: self.width + other.width,
+            num_channels: self.num_channels,
+        }
+    }
+}
+
+impl Zero for IxCube {
+    fn zero() -> Self {
+        Self::new(0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Dimension for IxCube {
+    fn as_descriptor<T: Scalar>(&self) -> Descriptor {
+        Descriptor {
+            Width: self.width,
+            Height: self.width,
+            Depth: 6,
+            NumChannels: descriptor_channels::<T>(self.num_channels),
+            Flags: ArrayFlag::CUBEMAP.bits(),
+            Format: T::format(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.width * self.width * 6 * self.num_channels.to_usize().unwrap()
+    }
+
+    fn num_channels(&self) -> NumChannels {
+        self.num_channels
+    }
+}
+
+/// Spec of Layered Cubemap Array
+///
+/// Each layer is a cubemap, i.e. the depth of the array is `6 * layers`.
+#[derive(Debug, Clone, Copy, PartialEq, new)]
+pub struct IxCubeLayered {
+    /// Width (and height) of each face
+    pub width: usize,
+    /// Number of cubemaps
+    pub layers: usize,
+    #[new(default)]
+    pub num_channels: NumChannels,
+}
+
+impl From<(usize, usize)> for IxCubeLayered {
+    fn from((width, layers): (usize, usize)) -> IxCubeLayered {
+        IxCubeLayered {
+            width,
+            layers,
+            num_channels: NumChannels::One,
+        }
+    }
+}
+
+impl Add for IxCubeLayered {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        assert_eq!(self.num_channels, other.num_channels);
+        Self {
+            width: self.width + other.width,
+            layers: self.layers + other.layers,
+            num_channels: self.num_channels,
+        }
+    }
+}
+
+impl Zero for IxCubeLayered {
+    fn zero() -> Self {
+        Self::new(0, 0)
+    }
+
+    fn is_zero(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Dimension for IxCubeLayered {
+    fn as_descriptor<T: Scalar>(&self) -> Descriptor {
+        Descriptor {
+            Width: self.width,
+            Height: self.width,
+            Depth: 6 * self.layers,
+            NumChannels: descriptor_channels::<T>(self.num_channels),
+            Flags: (ArrayFlag::CUBEMAP | ArrayFlag::LAYERED).bits(),
+            Format: T::format(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.width * self.width * 6 * self.layers * self.num_channels.to_usize().unwrap()
+    }
+
+    fn num_channels(&self) -> NumChannels {
+        self.num_channels
+    }
+}
+
+/// Spec of 1D, 2D or 3D Array whose rank is determined at runtime
+///
+/// This is useful when the shape is read from data, e.g. a file header.
+/// The shape is given as `[width, height, depth]` truncated to the rank,
+/// i.e. in the same order as [Ix1], [Ix2] and [Ix3].
+///
+/// ```
+/// # use accel::*;
+/// let dim = DynDim::from_shape(&[3, 4]).unwrap();
+/// assert_eq!(dim.rank(), 2);
+/// assert_eq!(dim.len(), 12);
+/// assert!(DynDim::from_shape(&[1, 2, 3, 4]).is_err());
+/// ```
+///
+/// [Ix1]: ./struct.Ix1.html
+/// [Ix2]: ./struct.Ix2.html
+/// [Ix3]: ./struct.Ix3.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynDim {
+    /// `[width, height, depth]` where unused ones are zero
+    shape: [usize; 3],
+    rank: usize,
+    pub num_channels: NumChannels,
+}
+
+impl DynDim {
+    /// Create from a shape of rank 1, 2, or 3
+    ///
+    /// Returns [AccelError::InvalidValue] for other ranks.
+    ///
+    /// [AccelError::InvalidValue]: ../error/enum.AccelError.html#variant.InvalidValue
+    pub fn from_shape(shape: &[usize]) -> Result<Self> {
+        if shape.is_empty() || shape.len() > 3 {
+            return Err(AccelError::InvalidValue {
+                message: format!("Rank of array must be 1, 2, or 3, but {}", shape.len()),
+            });
+        }
+        let mut dim = [0; 3];
+        dim[..shape.len()].copy_from_slice(shape);
+        Ok(DynDim {
+            shape: dim,
+            rank: shape.len(),
+            num_channels: NumChannels::One,
+        })
+    }
+
+    /// Number of dimensions
+    pub fn rank(&self) -> usize {
+        self.rank
+    }
+
+    /// Shape of `rank` elements
+    pub fn shape(&self) -> &[usize] {
+        &self.shape[..self.rank]
+    }
+}
+
+impl Add for DynDim {
+    type Output = Self;
+    fn add(self, other: Self) -> Self {
+        assert_eq!(self.num_channels, other.num_channels);
+        let mut shape = self.shape;
+        for (s, o) in shape.iter_mut().zip(other.shape.iter()) {
+            *s += o;
+        }
+        Self {
+            shape,
+            rank: std::cmp::max(self.rank, other.rank),
+            num_channels: self.num_channels,
+        }
+    }
+}
+
+impl Zero for DynDim {
+    fn zero() -> Self {
+        DynDim::from_shape(&[0]).unwrap()
+    }
+
+    fn is_zero(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Dimension for DynDim {
+    fn as_descriptor<T: Scalar>(&self) -> Descriptor {
+        Descriptor {
+            Width: self.shape[0],
+            Height: self.shape[1],
+            Depth: self.shape[2],
+            NumChannels: descriptor_channels::<T>(self.num_channels),
+            Flags: ArrayFlag::empty().bits(),
+            Format: T::format(),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.shape().iter().product::<usize>() * self.num_channels.to_usize().unwrap()
+    }
+
+    fn num_channels(&self) -> NumChannels {
+        self.num_channels
+    }
+}
+
+/// Dimension whose coordinates are represented by itself
+///
+/// Cubemaps are not included since their coordinates need the face index.
+pub trait Coordinates: Dimension {
+    /// Every coordinate in row-major order, i.e. `width` is the fastest
+    ///
+    /// Each coordinate points a "CUDA Array element", and `num_channels` of it is the same as `self`.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// let dim: Ix2 = (2, 3).into();
+    /// let coords: Vec<(usize, usize)> = dim.indices().map(|ix| (ix.width, ix.height)).collect();
+    /// assert_eq!(coords, vec![(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]);
+    /// ```
+    fn indices(&self) -> impl Iterator<Item = Self>;
+}
+
+impl Coordinates for Ix1 {
+    fn indices(&self) -> impl Iterator<Item = Self> {
+        let num_channels = self.num_channels;
+        (0..self.width).map(move |width| Ix1 {
+            width,
+            num_channels,
+        })
+    }
+}
+
+impl Coordinates for Ix2 {
+    fn indices(&self) -> impl Iterator<Item = Self> {
+        let Ix2 {
+            width,
+            height,
+            num_channels,
+        } = *self;
+        (0..height).flat_map(move |y| {
+            (0..width).map(move |x| Ix2 {
+                width: x,
+                height: y,
+                num_channels,
+            })
+        })
+    }
+}
+
+impl Coordinates for Ix3 {
+    fn indices(&self) -> impl Iterator<Item = Self> {
+        let Ix3 {
+            width,
+            height,
+            depth,
+            num_channels,
+        } = *self;
+        (0..depth).flat_map(move |z| {
+            (0..height).flat_map(move |y| {
+                (0..width).map(move |x| Ix3 {
+                    width: x,
+                    height: y,
+                    depth: z,
+                    num_channels,
+                })
+            })
+        })
+    }
+}
+
+impl Coordinates for Ix1Layered {
+    fn indices(&self) -> impl Iterator<Item = Self> {
+        let Ix1Layered {
+            width,
+            depth,
+            num_channels,
+        } = *self;
+        (0..depth).flat_map(move |z| {
+            (0..width).map(move |x| Ix1Layered {
+                width: x,
+                depth: z,
+                num_channels,
+            })
+        })
+    }
+}
+
+impl Coordinates for Ix2Layered {
+    fn indices(&self) -> impl Iterator<Item = Self> {
+        let Ix2Layered {
+            width,
+            height,
+            depth,
+            num_channels,
+        } = *self;
+        (0..depth).flat_map(move |z| {
+            (0..height).flat_map(move |y| {
+                (0..width).map(move |x| Ix2Layered {
+                    width: x,
+                    height: y,
+                    depth: z,
+                    num_channels,
+                })
+            })
+        })
+    }
+}
+
 bitflags::bitflags! {
     pub struct ArrayFlag: u32 {
         /// If set, the CUDA array is a collection of layers, where each layer is either a 1D or a 2D array and the Depth member of CUDA_ARRAY3D_DESCRIPTOR specifies the number of layers, not the depth of a 3D array.
@@ -398,3 +823,172 @@ bitflags::bitflags! {
         const COLOR_ATTACHMENT = 0x20;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn len_from_descriptor<Dim: Dimension>(dim: &Dim) -> usize {
+        let desc = dim.as_descriptor::<f32>();
+        desc.Width
+            * std::cmp::max(desc.Height, 1)
+            * std::cmp::max(desc.Depth, 1)
+            * desc.NumChannels as usize
+    }
+
+    #[test]
+    fn vector_channels() {
+        let mut dim = Ix1::new(3);
+        assert_eq!(dim.as_descriptor::<Float2>().NumChannels, 2);
+        dim.num_channels = NumChannels::Two;
+        assert_eq!(dim.as_descriptor::<Float2>().NumChannels, 4);
+    }
+
+    #[should_panic(expected = "CUDA array supports 1, 2 or 4 channels")]
+    #[test]
+    fn vector_channels_unsupported() {
+        let mut dim = Ix2::new(3, 4);
+        dim.num_channels = NumChannels::Two;
+        let _ = dim.as_descriptor::<Float4>();
+    }
+
+    #[test]
+    fn len_ix1() {
+        let dim = Ix1::new(3);
+        assert_eq!(dim.len(), 3);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+        let dim = Ix1 {
+            width: 3,
+            num_channels: NumChannels::Four,
+        };
+        assert_eq!(dim.len(), 12);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+    }
+
+    #[test]
+    fn len_ix2() {
+        let dim = Ix2::new(3, 4);
+        assert_eq!(dim.len(), 12);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+        let dim = Ix2 {
+            width: 3,
+            height: 4,
+            num_channels: NumChannels::Two,
+        };
+        assert_eq!(dim.len(), 24);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+    }
+
+    #[test]
+    fn len_ix3() {
+        let dim = Ix3::new(3, 4, 5);
+        assert_eq!(dim.len(), 60);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+    }
+
+    #[test]
+    fn len_dyn_dim() -> Result<()> {
+        let dim = DynDim::from_shape(&[3])?;
+        assert_eq!(dim.len(), 3);
+        assert_eq!(
+            dim.as_descriptor::<f32>(),
+            Ix1::new(3).as_descriptor::<f32>()
+        );
+        let dim = DynDim::from_shape(&[3, 4])?;
+        assert_eq!(dim.len(), 12);
+        assert_eq!(
+            dim.as_descriptor::<f32>(),
+            Ix2::new(3, 4).as_descriptor::<f32>()
+        );
+        let dim = DynDim::from_shape(&[3, 4, 5])?;
+        assert_eq!(dim.len(), 60);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+        assert!(DynDim::from_shape(&[]).is_err());
+        assert!(DynDim::from_shape(&[3, 4, 5, 6]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn len_ix1_layered() {
+        let dim = Ix1Layered::new(3, 4);
+        assert_eq!(dim.len(), 12);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+    }
+
+    #[test]
+    fn len_ix2_layered() {
+        let dim = Ix2Layered::new(3, 4, 5);
+        assert_eq!(dim.len(), 60);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+    }
+
+    #[test]
+    fn reshape() -> Result<()> {
+        let dim = Ix2::new(3, 4);
+        assert_eq!(dim.reshape(Ix1::new(12))?, Ix1::new(12));
+        assert_eq!(dim.reshape(Ix3::new(2, 2, 3))?, Ix3::new(2, 2, 3));
+        assert_eq!(
+            dim.reshape(Ix2::new(5, 2)),
+            Err(AccelError::ShapeMismatch { from: 12, to: 10 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn indices_ix2() {
+        let dim: Ix2 = (2, 3).into();
+        let indices: Vec<Ix2> = dim.indices().collect();
+        assert_eq!(indices.len(), 6);
+        assert_eq!(
+            indices,
+            vec![
+                Ix2::new(0, 0),
+                Ix2::new(1, 0),
+                Ix2::new(0, 1),
+                Ix2::new(1, 1),
+                Ix2::new(0, 2),
+                Ix2::new(1, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn indices_len() {
+        assert_eq!(Ix1::new(5).indices().count(), 5);
+        assert_eq!(Ix3::new(2, 3, 4).indices().count(), 24);
+        assert_eq!(Ix3::new(2, 3, 4).indices().last(), Some(Ix3::new(1, 2, 3)));
+        assert_eq!(Ix1Layered::new(2, 3).indices().count(), 6);
+        assert_eq!(Ix2Layered::new(2, 3, 4).indices().count(), 24);
+        assert_eq!(Ix2::new(0, 3).indices().count(), 0);
+    }
+
+    #[test]
+    fn into_layered() {
+        let dim = Ix2::new(3, 4);
+        assert_eq!(dim.into_ix1_layered(), Ix1Layered::new(3, 4));
+        assert_eq!(dim.into_ix1_layered().into_ix2(), dim);
+        let dim = Ix3::new(3, 4, 5);
+        assert_eq!(dim.into_ix2_layered(), Ix2Layered::new(3, 4, 5));
+        assert_eq!(dim.into_ix2_layered().into_ix3(), dim);
+    }
+
+    #[test]
+    fn len_ix_cube() {
+        let dim = IxCube::new(3);
+        assert_eq!(dim.len(), 54);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+        let desc = dim.as_descriptor::<f32>();
+        assert_eq!(desc.Width, desc.Height);
+        assert_eq!(desc.Depth, 6);
+    }
+
+    #[test]
+    fn len_ix_cube_layered() {
+        let dim = IxCubeLayered::new(3, 2);
+        assert_eq!(dim.len(), 108);
+        assert_eq!(dim.len(), len_from_descriptor(&dim));
+        let desc = dim.as_descriptor::<f32>();
+        assert_eq!(desc.Width, desc.Height);
+        assert_eq!(desc.Depth, 12);
+    }
+}
@@ -39,6 +39,7 @@
 //! [Continuous]: ./trait.Continuous.html
 //! [Allocatable]: ./trait.Allocatable.html
 
+mod arena;
 mod array;
 mod device;
 mod dimension;
@@ -47,7 +48,11 @@ mod page_locked;
 mod registered;
 mod scalar;
 mod slice;
+mod texture;
 
+pub mod virtual_mem;
+
+pub use arena::*;
 pub use array::*;
 pub use device::*;
 pub use dimension::*;
@@ -55,11 +60,18 @@ pub use info::*;
 pub use page_locked::*;
 pub use registered::*;
 pub use scalar::*;
+pub use slice::{device_ordinal, guard_pointer_context, is_managed};
+pub use texture::*;
 
 use crate::*;
 use cuda::*;
 use num_traits::Zero;
-use std::{ffi::c_void, mem::MaybeUninit};
+use std::{
+    ffi::c_void,
+    fmt::Debug,
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 /// Memory type
 ///
@@ -122,6 +134,21 @@ pub enum MemoryType {
     Array,
 }
 
+/// How a copy is issued, e.g. by [Array::copy]
+///
+/// This allows callers to switch between blocking and stream-ordered transfers
+/// without calling different methods.
+///
+/// [Array::copy]: ./struct.Array.html#method.copy
+#[derive(Clone, Copy)]
+pub enum CopyMode<'stream> {
+    /// Blocking copy, which completes before the method returns
+    Sync,
+    /// Copy issued asynchronously on the stream.
+    /// Both source and destination must not be modified or dropped until the stream is synchronized.
+    Async(&'stream Stream),
+}
+
 /// Has unique head address and allocated size.
 pub trait Memory {
     /// Scalar type of each element
@@ -138,6 +165,32 @@ pub trait Memory {
 
     /// Get memory type, See [MemoryType](./enum.MemoryType.html) for detail.
     fn memory_type(&self) -> MemoryType;
+
+    /// Check if kernels launched in `ctx` can access this memory through its pointer
+    ///
+    /// - Managed memory, e.g. [DeviceMemory], is accessible from any context
+    /// - Device memory is accessible from its own context,
+    ///   and from contexts on other devices capable of peer access to it
+    /// - Page-locked host memory is mapped into the device if the device supports unified addressing
+    /// - Usual host memory and [Array] are not accessible through pointer
+    ///
+    /// Peer access must be enabled separately, since this checks only the capability of devices.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// assert!(mem.accessible_from(&ctx));
+    /// let host = vec![0_i32; 12];
+    /// assert!(!host.as_slice().accessible_from(&ctx));
+    /// ```
+    ///
+    /// [DeviceMemory]: ./struct.DeviceMemory.html
+    /// [Array]: ./struct.Array.html
+    fn accessible_from(&self, ctx: &Context) -> bool {
+        slice::accessible_from(self.head_addr(), self.memory_type(), ctx)
+    }
 }
 
 /// Copy data from one to another
@@ -226,21 +279,142 @@ pub trait Memory {
 /// dest.copy_from(&src); // will panic
 /// ```
 ///
+/// - [Memcpy::try_copy_from] returns an error instead of panic
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mut dest = DeviceMemory::<i32>::zeros(&ctx, 24);
+/// let src = PageLockedMemory::<i32>::zeros(&ctx, 12);
+/// assert!(dest.try_copy_from(&src).is_err());
+/// ```
+///
 /// Panic
 /// -----
-/// - `self` and `src` are identical
+/// - `self` and `src` are identical (only for [Memcpy::copy_from])
 /// - if `self` nad `src` belong to different context
-/// - if the size memory size mismathes
+/// - if the size memory size mismathes (only for [Memcpy::copy_from])
+///
+/// [Memcpy::try_copy_from]: ./trait.Memcpy.html#tymethod.try_copy_from
+/// [Memcpy::copy_from]: ./trait.Memcpy.html#method.copy_from
 pub trait Memcpy<Target: ?Sized>: Memory
 where
     Target: Memory<Elem = Self::Elem> + Memcpy<Self>,
 {
-    fn copy_from(&mut self, source: &Target);
+    /// Copy from `source`, returning an error if the sizes mismatch or the CUDA API fails
+    fn try_copy_from(&mut self, source: &Target) -> error::Result<()>;
+
+    fn copy_from(&mut self, source: &Target) {
+        if let Err(e) = self.try_copy_from(source) {
+            panic!("memcpy failed: {}", e);
+        }
+    }
+
     fn copy_to(&self, destination: &mut Target) {
         destination.copy_from(self);
     }
 }
 
+/// Check the numbers of elements of destination and source are the same
+pub(crate) fn check_size(dest: usize, src: usize) -> error::Result<()> {
+    if dest != src {
+        return Err(error::AccelError::SizeMismatch { dest, src });
+    }
+    Ok(())
+}
+
+/// Check the destination and source are not the same memory
+pub(crate) fn check_distinct<T>(dest: *const T, src: *const T) -> error::Result<()> {
+    if dest == src {
+        return Err(error::AccelError::InvalidValue {
+            message: "Source and destination of a copy are the same memory".into(),
+        });
+    }
+    Ok(())
+}
+
+/// Panic version of [check_size] with a message telling which side is larger
+#[track_caller]
+pub(crate) fn assert_same_size(dest: usize, src: usize) {
+    if let Err(e) = check_size(dest, src) {
+        panic!("{}", e);
+    }
+}
+
+/// Object-safe counterpart of [Memory] with a fixed element type
+///
+/// [Memory] cannot be used as a trait object because of its associated type.
+/// This trait is implemented for [DeviceMemory], [PageLockedMemory], [RegisteredMemory] and [Array],
+/// and allows us to store different kinds of memories in a collection:
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mems: Vec<Box<dyn DynMemory<i32>>> = vec![
+///     Box::new(DeviceMemory::<i32>::zeros(&ctx, 12)),
+///     Box::new(PageLockedMemory::<i32>::zeros(&ctx, 12)),
+/// ];
+/// let mut dest = vec![1_i32; 12];
+/// for mem in &mems {
+///     mem.dyn_copy_to(&mut dest);
+/// }
+/// ```
+///
+/// [Memory]: ./trait.Memory.html
+/// [DeviceMemory]: ./struct.DeviceMemory.html
+/// [PageLockedMemory]: ./struct.PageLockedMemory.html
+/// [RegisteredMemory]: ./struct.RegisteredMemory.html
+/// [Array]: ./struct.Array.html
+pub trait DynMemory<T: Scalar> {
+    /// Same as [Memory::head_addr](./trait.Memory.html#tymethod.head_addr)
+    fn dyn_head_addr(&self) -> *const T;
+
+    /// Same as [Memory::num_elem](./trait.Memory.html#tymethod.num_elem)
+    fn dyn_num_elem(&self) -> usize;
+
+    /// Same as [Memory::memory_type](./trait.Memory.html#tymethod.memory_type)
+    fn dyn_memory_type(&self) -> MemoryType;
+
+    /// Copy the content into `dest`
+    fn dyn_copy_to(&self, dest: &mut [T]);
+
+    /// Copy the content of `src` into this memory
+    fn dyn_copy_from(&mut self, src: &[T]);
+}
+
+macro_rules! impl_dyn_memory {
+    ($t:ty $(,$dim:ident)*) => {
+        impl<T: Scalar $(,$dim: Dimension)*> DynMemory<T> for $t {
+            fn dyn_head_addr(&self) -> *const T {
+                self.head_addr()
+            }
+
+            fn dyn_num_elem(&self) -> usize {
+                self.num_elem()
+            }
+
+            fn dyn_memory_type(&self) -> MemoryType {
+                self.memory_type()
+            }
+
+            fn dyn_copy_to(&self, dest: &mut [T]) {
+                dest.copy_from(self);
+            }
+
+            fn dyn_copy_from(&mut self, src: &[T]) {
+                self.copy_from(src);
+            }
+        }
+    };
+}
+
+impl_dyn_memory!(DeviceMemory<T>);
+impl_dyn_memory!(PageLockedMemory<T>);
+impl_dyn_memory!(RegisteredMemory<'_, T>);
+impl_dyn_memory!(Array<T, Dim>, Dim);
+
 /// Set all elements by `value`
 ///
 /// Examples
@@ -326,8 +500,438 @@ pub trait Allocatable: Contexted + Memset + Sized {
     }
 }
 
+/// Allocation with the same shape and context as an existing memory
+///
+/// Implemented for memories which know their own shape and context, e.g. [DeviceMemory].
+pub trait AllocateLike: Allocatable {
+    /// Shape of this memory
+    fn shape(&self) -> Self::Shape;
+
+    /// Context where this memory is allocated
+    fn context(&self) -> &Context;
+
+    /// Allocate a memory with the same shape and context as `other` without initialization
+    ///
+    /// Safety
+    /// ------
+    /// - Cause undefined behavior when read before write
+    unsafe fn uninitialized_like(other: &Self) -> Self {
+        Self::uninitialized(other.context(), other.shape())
+    }
+
+    /// Allocate a memory with the same shape and context as `other` initialized by zero
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let a = DeviceMemory::<f32>::from_elem(&ctx, 12, 1.0);
+    /// let b = DeviceMemory::zeros_like(&a);
+    /// assert_eq!(b.num_elem(), 12);
+    /// ```
+    fn zeros_like(other: &Self) -> Self {
+        Self::zeros(other.context(), other.shape())
+    }
+}
+
+/// Allocate a [DeviceMemory] of `n` elements initialized by zero
+///
+/// Shorthand for `DeviceMemory::<T>::zeros(ctx, n)`
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mem = device_zeros::<f32>(&ctx, 4);
+/// assert_eq!(mem.to_vec(), vec![0.0; 4]);
+/// ```
+///
+/// Panic
+/// ------
+/// - if `n` is zero
+pub fn device_zeros<T: Scalar>(ctx: &Context, n: usize) -> DeviceMemory<T> {
+    DeviceMemory::zeros(ctx, n)
+}
+
+/// Allocate a [DeviceMemory] of `n` elements initialized by one
+///
+/// Panic
+/// ------
+/// - if `n` is zero
+pub fn device_ones<T: Scalar>(ctx: &Context, n: usize) -> DeviceMemory<T> {
+    DeviceMemory::from_elem(ctx, n, T::one())
+}
+
+/// Allocate a [PageLockedMemory] of `n` elements initialized by zero
+///
+/// Panic
+/// ------
+/// - if `n` is zero
+pub fn host_zeros<T: Scalar>(ctx: &Context, n: usize) -> PageLockedMemory<T> {
+    PageLockedMemory::zeros(ctx, n)
+}
+
+/// Allocate a [PageLockedMemory] of `n` elements initialized by one
+///
+/// Panic
+/// ------
+/// - if `n` is zero
+pub fn host_ones<T: Scalar>(ctx: &Context, n: usize) -> PageLockedMemory<T> {
+    PageLockedMemory::from_elem(ctx, n, T::one())
+}
+
+/// Dot product of two memories computed on the host
+///
+/// Both memories are copied into host vectors, so that this also works for memories
+/// not accessible from the host, e.g. [Array].
+/// This is intended as a reference to validate results of GPU kernels in tests.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let a = DeviceMemory::<i32>::from_elem(&ctx, 4, 2);
+/// let b = vec![1, 2, 3, 4];
+/// assert_eq!(dot_host(&a, b.as_slice()).unwrap(), 20);
+/// ```
+///
+/// Returns [AccelError::SizeMismatch] if the numbers of elements are different.
+///
+/// [Array]: ./struct.Array.html
+/// [AccelError::SizeMismatch]: ../error/enum.AccelError.html#variant.SizeMismatch
+pub fn dot_host<T, A, B>(a: &A, b: &B) -> error::Result<T>
+where
+    T: Scalar,
+    A: ToHostVec<T> + ?Sized,
+    B: ToHostVec<T> + ?Sized,
+{
+    check_size(a.num_elem(), b.num_elem())?;
+    let x = a.to_host_vec()?;
+    let y = b.to_host_vec()?;
+    Ok(x.into_iter()
+        .zip(y)
+        .fold(T::zero(), |acc, (x, y)| acc + x * y))
+}
+
+/// Assert two memories have the same elements after copying them into host vectors
+///
+/// This is intended to compare results of GPU kernels in tests,
+/// and works for memories not accessible from the host, e.g. [Array].
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let a = DeviceMemory::<i32>::from_elem(&ctx, 4, 2);
+/// assert_memory_eq(&a, [2, 2, 2, 2].as_ref());
+/// ```
+///
+/// Panic
+/// -----
+/// - if the numbers of elements are different
+/// - with the first differing index and values if elements are different
+/// - if the copy fails
+///
+/// [Array]: ./struct.Array.html
+#[track_caller]
+pub fn assert_memory_eq<T, A, B>(a: &A, b: &B)
+where
+    T: Scalar + Debug,
+    A: ToHostVec<T> + ?Sized,
+    B: ToHostVec<T> + ?Sized,
+{
+    assert_eq!(
+        a.num_elem(),
+        b.num_elem(),
+        "Numbers of elements are different"
+    );
+    let x = a.to_host_vec().expect("Failed to copy memory into host");
+    let y = b.to_host_vec().expect("Failed to copy memory into host");
+    if let Some(i) = (0..x.len()).find(|&i| x[i] != y[i]) {
+        panic!("Memories differ at index {}: {:?} != {:?}", i, x[i], y[i]);
+    }
+}
+
+mod host_vec {
+    use super::*;
+
+    /// Memory which can be copied into a new host vector, used by [dot_host] and [assert_memory_eq]
+    ///
+    /// `Memory<Elem = T>` is not inferred from the impl for `[T]`
+    /// once `Memcpy` is bounded in a generic function,
+    /// and this trait keeps such bounds in one place.
+    ///
+    /// [dot_host]: ../fn.dot_host.html
+    /// [assert_memory_eq]: ../fn.assert_memory_eq.html
+    pub trait ToHostVec<T: Scalar>: Memory<Elem = T> {
+        /// Copy the whole of memory into a new host vector
+        fn to_host_vec(&self) -> error::Result<Vec<T>>;
+    }
+
+    impl<T, A> ToHostVec<T> for A
+    where
+        T: Scalar,
+        A: Memcpy<[T], Elem = T> + ?Sized,
+        [T]: Memory<Elem = T> + Memcpy<A>,
+    {
+        fn to_host_vec(&self) -> error::Result<Vec<T>> {
+            let mut v = vec![T::zero(); self.num_elem()];
+            v.as_mut_slice().try_copy_from(self)?;
+            Ok(v)
+        }
+    }
+}
+
+use host_vec::ToHostVec;
+
 /// Memory which has continuous 1D index, i.e. can be treated as a Rust slice
 pub trait Continuous: Memory {
     fn as_slice(&self) -> &[Self::Elem];
     fn as_mut_slice(&mut self) -> &mut [Self::Elem];
+
+    /// Copy data from `src` by chunks of `chunk_len` elements
+    ///
+    /// `cancel` is checked between chunks, and the copy stops if it is set.
+    /// Returns the number of bytes which have been copied.
+    /// If `chunk_len` is zero, [transfer::recommended_chunk_len] for the device of `self` or `src`
+    /// is used instead.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # use std::sync::atomic::AtomicBool;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// let src = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+    /// let cancel = AtomicBool::new(false);
+    /// let bytes = dest.copy_from_chunked(&src, 5, &cancel);
+    /// assert_eq!(bytes, 12 * std::mem::size_of::<i32>());
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the sizes of `self` and `src` are different
+    ///
+    /// [transfer::recommended_chunk_len]: ../transfer/fn.recommended_chunk_len.html
+    fn copy_from_chunked<Src>(&mut self, src: &Src, chunk_len: usize, cancel: &AtomicBool) -> usize
+    where
+        Src: Continuous<Elem = Self::Elem> + ?Sized,
+    {
+        self.copy_from_chunked_with_progress(src, chunk_len, cancel, |_, _| {})
+    }
+
+    /// [Continuous::copy_from_chunked] reporting progress after each chunk is copied
+    ///
+    /// `progress` receives `(bytes_done, bytes_total)`,
+    /// and `bytes_done` equals to `bytes_total` at the last call unless the copy is cancelled.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # use std::sync::atomic::AtomicBool;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// let src = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+    /// let cancel = AtomicBool::new(false);
+    /// dest.copy_from_chunked_with_progress(&src, 5, &cancel, |done, total| {
+    ///     println!("{}/{} bytes", done, total)
+    /// });
+    /// ```
+    ///
+    /// [Continuous::copy_from_chunked]: ./trait.Continuous.html#method.copy_from_chunked
+    fn copy_from_chunked_with_progress<Src, P>(
+        &mut self,
+        src: &Src,
+        chunk_len: usize,
+        cancel: &AtomicBool,
+        mut progress: P,
+    ) -> usize
+    where
+        Src: Continuous<Elem = Self::Elem> + ?Sized,
+        P: FnMut(usize, usize),
+    {
+        let dest = self.as_mut_slice();
+        let src = src.as_slice();
+        assert_same_size(dest.num_elem(), src.num_elem());
+        let chunk_len = if chunk_len == 0 {
+            transfer::default_chunk_len(dest, src)
+        } else {
+            chunk_len
+        };
+        let total = src.len() * Self::Elem::size_of();
+        let mut copied = 0;
+        for (dest, src) in dest.chunks_mut(chunk_len).zip(src.chunks(chunk_len)) {
+            dest.copy_from(src);
+            copied += src.len();
+            progress(copied * Self::Elem::size_of(), total);
+            if cancel.load(Ordering::SeqCst) {
+                break;
+            }
+        }
+        copied * Self::Elem::size_of()
+    }
+
+    /// Copy the first `min(self.num_elem(), src.num_elem())` elements from `src`
+    ///
+    /// Unlike [Memcpy::copy_from] which requires the sizes to be the same,
+    /// this copies the common prefix and leaves the remaining elements of `self` untouched.
+    /// Returns the number of copied elements.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// let src = PageLockedMemory::<i32>::from_elem(&ctx, 4, 1);
+    /// assert_eq!(dest.copy_prefix_from(&src), 4);
+    /// ```
+    ///
+    /// [Memcpy::copy_from]: ./trait.Memcpy.html#tymethod.copy_from
+    fn copy_prefix_from<Src>(&mut self, src: &Src) -> usize
+    where
+        Src: Continuous<Elem = Self::Elem> + ?Sized,
+    {
+        let n = std::cmp::min(self.num_elem(), src.num_elem());
+        self.as_mut_slice()[..n].copy_from(&src.as_slice()[..n]);
+        n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+
+    #[test]
+    fn device_zeros_ones() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        assert_eq!(device_zeros::<f32>(&context, 4).to_vec(), vec![0.0; 4]);
+        assert_eq!(device_ones::<f32>(&context, 4).to_vec(), vec![1.0; 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn host_zeros_ones() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        assert_eq!(host_zeros::<i32>(&context, 4).to_vec(), vec![0; 4]);
+        assert_eq!(host_ones::<i32>(&context, 4).to_vec(), vec![1; 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn dot_host() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut a = DeviceMemory::<f32>::zeros(&context, 4);
+        a.copy_from(&[1.0, 2.0, 3.0, 4.0]);
+        let mut b = DeviceMemory::<f32>::zeros(&context, 4);
+        b.copy_from(&[4.0, 3.0, 2.0, 1.0]);
+        assert_eq!(super::dot_host(&a, &b)?, 20.0);
+
+        let c = DeviceMemory::<f32>::zeros(&context, 3);
+        assert_eq!(
+            super::dot_host(&a, &c),
+            Err(error::AccelError::SizeMismatch { dest: 4, src: 3 })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn assert_memory_eq() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let a = DeviceMemory::<i32>::from_elem(&context, 12, 3);
+        let b = PageLockedMemory::<i32>::from_elem(&context, 12, 3);
+        super::assert_memory_eq(&a, &b);
+        let array = Array::<i32, Ix2>::from_elem(&context, (3, 4).into(), 3);
+        super::assert_memory_eq(&array, &a);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Memories differ at index 5: 3 != 4")]
+    #[test]
+    fn assert_memory_eq_differ() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let a = DeviceMemory::<i32>::from_elem(&context, 12, 3);
+        let mut b = vec![3; 12];
+        b[5] = 4;
+        super::assert_memory_eq(&a, &b);
+    }
+
+    #[test]
+    fn dyn_memory() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mems: Vec<Box<dyn DynMemory<i32>>> = vec![
+            Box::new(DeviceMemory::<i32>::zeros(&context, 12)),
+            Box::new(PageLockedMemory::<i32>::zeros(&context, 12)),
+        ];
+        for (i, mem) in mems.iter_mut().enumerate() {
+            mem.dyn_copy_from(&[i as i32; 12]);
+        }
+        assert_eq!(mems[0].dyn_memory_type(), MemoryType::Device);
+        assert_eq!(mems[1].dyn_memory_type(), MemoryType::PageLocked);
+        for (i, mem) in mems.iter().enumerate() {
+            let mut dest = vec![0; 12];
+            mem.dyn_copy_to(&mut dest);
+            assert_eq!(dest, vec![i as i32; 12]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn zeros_like() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let a = DeviceMemory::<i32>::from_elem(&context, 12, 3);
+        let b = DeviceMemory::zeros_like(&a);
+        assert_eq!(b.shape(), a.shape());
+        assert_eq!(b.to_vec(), vec![0; 12]);
+
+        let a = PageLockedMemory::<i32>::from_elem(&context, 12, 3);
+        let b = PageLockedMemory::zeros_like(&a);
+        assert_eq!(b.shape(), a.shape());
+        assert_eq!(b.to_vec(), vec![0; 12]);
+
+        let a = Array::<i32, Ix2>::from_elem(&context, (3, 4).into(), 3);
+        let b = Array::zeros_like(&a);
+        assert_eq!(b.shape(), a.shape());
+        Ok(())
+    }
+
+    #[test]
+    fn try_copy_from_mismatch() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mismatch = Err(error::AccelError::SizeMismatch { dest: 24, src: 12 });
+
+        let src = PageLockedMemory::<i32>::zeros(&context, 12);
+        let mut dest = DeviceMemory::<i32>::zeros(&context, 24);
+        assert_eq!(dest.try_copy_from(&src), mismatch);
+
+        let mut dest = vec![0_i32; 24];
+        assert_eq!(dest.try_copy_from(&src), mismatch);
+
+        let mut array = Array::<i32, Ix2>::zeros(&context, (4, 6).into());
+        assert_eq!(array.try_copy_from(&src), mismatch);
+
+        let mut dest = DeviceMemory::<i32>::zeros(&context, 12);
+        assert_eq!(dest.try_copy_from(&src), Ok(()));
+        Ok(())
+    }
+
+    #[should_panic(
+        expected = "memcpy failed: Destination has 24 elements but source has 12 elements"
+    )]
+    #[test]
+    fn copy_from_mismatch_message() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let src = PageLockedMemory::<i32>::zeros(&context, 12);
+        let mut dest = DeviceMemory::<i32>::zeros(&context, 24);
+        dest.copy_from(&src);
+    }
 }
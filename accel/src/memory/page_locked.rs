@@ -19,12 +19,20 @@ pub struct PageLockedMemory<T> {
     ptr: *mut T,
     size: usize,
     context: Context,
+    /// Length in bytes of the huge-page mapping if allocated by `new_hugepages`
+    #[cfg(all(feature = "hugepages", target_os = "linux"))]
+    mapped: Option<usize>,
 }
 
 impl<T> Drop for PageLockedMemory<T> {
     fn drop(&mut self) {
+        #[cfg(all(feature = "hugepages", target_os = "linux"))]
+        if let Some(len) = self.mapped {
+            unmap_hugepages(&self.context, self.ptr as *mut libc::c_void, len);
+            return;
+        }
         if let Err(e) = unsafe { contexted_call!(self, cuMemFreeHost, self.ptr as *mut _) } {
-            log::error!("Cannot free page-locked memory: {:?}", e);
+            error::report_error("Cannot free page-locked memory", &e);
         }
     }
 }
@@ -79,17 +87,322 @@ impl<T: Scalar> Continuous for PageLockedMemory<T> {
 impl<T: Scalar> Allocatable for PageLockedMemory<T> {
     type Shape = usize;
     unsafe fn uninitialized(context: &Context, size: usize) -> Self {
+        Self::alloc(context, size, 0)
+    }
+}
+
+impl<T: Scalar> AllocateLike for PageLockedMemory<T> {
+    fn shape(&self) -> Self::Shape {
+        self.size
+    }
+
+    fn context(&self) -> &Context {
+        &self.context
+    }
+}
+
+impl<T: Scalar> PageLockedMemory<T> {
+    /// Start building a page-locked memory of `size` elements with allocation flags
+    ///
+    /// See [PinnedBuilder] for detail.
+    ///
+    /// [PinnedBuilder]: ./struct.PinnedBuilder.html
+    pub fn builder(size: usize) -> PinnedBuilder {
+        PinnedBuilder::new(size)
+    }
+
+    /// Copy into a usual `Vec` on the heap, and free this page-locked memory
+    ///
+    /// Page-locked memory cannot be converted into `Vec` without copying,
+    /// since it is allocated by the CUDA driver instead of the global allocator.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = PageLockedMemory::<i32>::from_elem(&ctx, 4, 3);
+    /// assert_eq!(mem.into_vec(), vec![3; 4]);
+    /// ```
+    pub fn into_vec(self) -> Vec<T> {
+        self.as_slice().to_vec()
+    }
+
+    /// Allocate by [cuMemHostAlloc] with `flags`, which equals to `cuMemAllocHost` if `flags` is zero
+    ///
+    /// [cuMemHostAlloc]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
+    unsafe fn alloc(context: &Context, size: usize, flags: u32) -> Self {
         assert!(size > 0, "Zero-sized malloc is forbidden");
-        let ptr = contexted_new!(context, cuMemAllocHost_v2, size * std::mem::size_of::<T>())
-            .expect("Cannot allocate page-locked memory");
+        let ptr = contexted_new!(
+            context,
+            cuMemHostAlloc,
+            size * std::mem::size_of::<T>(),
+            flags
+        )
+        .expect("Cannot allocate page-locked memory");
         Self {
             ptr: ptr as *mut T,
             size,
             context: context.clone(),
+            #[cfg(all(feature = "hugepages", target_os = "linux"))]
+            mapped: None,
         }
     }
 }
 
+/// Builder of [PageLockedMemory] composing flags of [cuMemHostAlloc]
+///
+/// Without any flag, this allocates the same memory as [Allocatable::zeros].
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mem = PageLockedMemory::<f32>::builder(1024)
+///     .portable()
+///     .write_combined()
+///     .build(&ctx);
+/// assert_eq!(mem.num_elem(), 1024);
+/// ```
+///
+/// [PageLockedMemory]: ./struct.PageLockedMemory.html
+/// [Allocatable::zeros]: ./trait.Allocatable.html#method.zeros
+/// [cuMemHostAlloc]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PinnedBuilder {
+    size: usize,
+    flags: u32,
+}
+
+impl PinnedBuilder {
+    /// Builder of `size` elements without any flag
+    pub fn new(size: usize) -> Self {
+        PinnedBuilder { size, flags: 0 }
+    }
+
+    /// `CU_MEMHOSTALLOC_PORTABLE`: The memory is regarded as page-locked by all contexts,
+    /// not only by the context allocating it
+    pub fn portable(mut self) -> Self {
+        self.flags |= CU_MEMHOSTALLOC_PORTABLE;
+        self
+    }
+
+    /// `CU_MEMHOSTALLOC_DEVICEMAP`: Map the memory into the address space of the device
+    pub fn device_map(mut self) -> Self {
+        self.flags |= CU_MEMHOSTALLOC_DEVICEMAP;
+        self
+    }
+
+    /// `CU_MEMHOSTALLOC_WRITECOMBINED`: Allocate as write-combined memory
+    ///
+    /// This is faster to be transferred to the device, but is very slow to be read from the host.
+    /// It is suitable for buffers only written by the host.
+    pub fn write_combined(mut self) -> Self {
+        self.flags |= CU_MEMHOSTALLOC_WRITECOMBINED;
+        self
+    }
+
+    /// Flags passed to `cuMemHostAlloc`
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    /// Allocate a page-locked memory initialized by zero
+    ///
+    /// Panic
+    /// -----
+    /// - if `size` is zero
+    pub fn build<T: Scalar>(self, context: &Context) -> PageLockedMemory<T> {
+        let mut mem = unsafe { PageLockedMemory::alloc(context, self.size, self.flags) };
+        mem.set(T::zero());
+        mem
+    }
+}
+
+#[cfg(all(feature = "numa", target_os = "linux"))]
+impl<T: Scalar> PageLockedMemory<T> {
+    /// Allocate page-locked memory of `size` elements initialized by zero, and bind it to NUMA `node`
+    ///
+    /// Allocating staging buffers on the NUMA node near the GPU improves the bandwidth
+    /// of transfers on multi-socket hosts.
+    /// The binding is a hint: pages are moved to `node` by [mbind] with `MPOL_MF_MOVE`,
+    /// and a failure is reported by `log::warn!` without failing the allocation.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = PageLockedMemory::<f32>::new_numa(&ctx, 1024, 0);
+    /// assert_eq!(mem.as_slice(), &[0.0; 1024][..]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `size` is zero
+    ///
+    /// [mbind]: https://man7.org/linux/man-pages/man2/mbind.2.html
+    pub fn new_numa(context: &Context, size: usize, node: usize) -> Self {
+        let mut mem = unsafe { Self::uninitialized(context, size) };
+        if let Err(e) = bind_numa(mem.ptr as *mut libc::c_void, size * T::size_of(), node) {
+            log::warn!(
+                "Failed to bind page-locked memory to NUMA node {}: {}",
+                node,
+                e
+            );
+        }
+        mem.set(T::zero());
+        mem
+    }
+}
+
+/// Set the memory policy of the range to `MPOL_BIND` on `node`, and move existing pages to it
+#[cfg(all(feature = "numa", target_os = "linux"))]
+fn bind_numa(addr: *mut libc::c_void, len: usize, node: usize) -> std::io::Result<()> {
+    const MPOL_BIND: libc::c_long = 2;
+    const MPOL_MF_MOVE: libc::c_ulong = 1 << 1;
+    let bits = 8 * std::mem::size_of::<libc::c_ulong>();
+    let mut mask: Vec<libc::c_ulong> = vec![0; node / bits + 1];
+    mask[node / bits] |= 1 << (node % bits);
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            addr,
+            len as libc::c_ulong,
+            MPOL_BIND,
+            mask.as_ptr(),
+            // The kernel regards the last bit as excluded
+            (mask.len() * bits + 1) as libc::c_ulong,
+            MPOL_MF_MOVE,
+        )
+    };
+    if ret != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Size of huge pages assumed in [PageLockedMemory::new_hugepages]
+///
+/// [PageLockedMemory::new_hugepages]: ./struct.PageLockedMemory.html#method.new_hugepages
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+const HUGE_PAGE_SIZE: usize = 2 << 20;
+
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+impl<T: Scalar> PageLockedMemory<T> {
+    /// Allocate page-locked memory of `size` elements initialized by zero on huge pages
+    ///
+    /// The memory is mapped by `mmap` with `MAP_HUGETLB`, and registered by `cuMemHostRegister`.
+    /// Huge pages reduce TLB misses for very large transfers.
+    /// The mapping is rounded up to a multiple of 2MB huge pages.
+    /// If huge pages are not available, e.g. no pages are reserved in `/proc/sys/vm/nr_hugepages`,
+    /// this falls back to [Allocatable::zeros] with a warning by `log::warn!`.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let mem = PageLockedMemory::<f32>::new_hugepages(&ctx, 1 << 20);
+    /// assert_eq!(mem.memory_type(), MemoryType::PageLocked);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if `size` is zero
+    ///
+    /// [Allocatable::zeros]: ./trait.Allocatable.html#method.zeros
+    pub fn new_hugepages(context: &Context, size: usize) -> Self {
+        assert!(size > 0, "Zero-sized malloc is forbidden");
+        let len = (size * T::size_of()).div_ceil(HUGE_PAGE_SIZE) * HUGE_PAGE_SIZE;
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_HUGETLB,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            log::warn!(
+                "Huge pages are not available, fall back to usual page-locked memory: {}",
+                std::io::Error::last_os_error()
+            );
+            return Self::zeros(context, size);
+        }
+        // Anonymous mapping is initialized by zero
+        if let Err(e) = unsafe { contexted_call!(context, cuMemHostRegister_v2, addr, len, 0) } {
+            log::warn!(
+                "Failed to register huge pages, fall back to usual page-locked memory: {}",
+                e
+            );
+            unsafe { libc::munmap(addr, len) };
+            return Self::zeros(context, size);
+        }
+        Self {
+            ptr: addr as *mut T,
+            size,
+            context: context.clone(),
+            mapped: Some(len),
+        }
+    }
+
+    /// Check if this memory is allocated on huge pages by [PageLockedMemory::new_hugepages]
+    ///
+    /// [PageLockedMemory::new_hugepages]: #method.new_hugepages
+    pub fn is_hugepages(&self) -> bool {
+        self.mapped.is_some()
+    }
+}
+
+/// Unregister and unmap a memory allocated by `new_hugepages`
+#[cfg(all(feature = "hugepages", target_os = "linux"))]
+fn unmap_hugepages(context: &Context, addr: *mut libc::c_void, len: usize) {
+    if let Err(e) = unsafe { contexted_call!(context, cuMemHostUnregister, addr) } {
+        error::report_error("Failed to unregister huge pages", &e);
+    }
+    if unsafe { libc::munmap(addr, len) } != 0 {
+        log::error!(
+            "Failed to unmap huge pages: {}",
+            std::io::Error::last_os_error()
+        );
+    }
+}
+
+/// Page-locked memory used as a staging buffer of asynchronous operations on a stream
+///
+/// The memory must not be freed until the operations complete.
+/// This guard keeps it alive, and `Drop` waits for the stream before freeing it.
+#[must_use = "The staging memory will be freed after synchronizing the stream immediately"]
+pub struct PinnedGuard<'stream, T> {
+    mem: Option<PageLockedMemory<T>>,
+    stream: &'stream Stream,
+}
+
+impl<T> Drop for PinnedGuard<'_, T> {
+    fn drop(&mut self) {
+        if self.mem.is_some() {
+            if let Err(e) = self.stream.sync() {
+                error::report_error("Failed to sync stream before freeing staging memory", &e);
+            }
+        }
+    }
+}
+
+impl<'stream, T> PinnedGuard<'stream, T> {
+    pub(crate) fn new(mem: PageLockedMemory<T>, stream: &'stream Stream) -> Self {
+        PinnedGuard {
+            mem: Some(mem),
+            stream,
+        }
+    }
+
+    /// Wait until the stream completes, and get the staging memory back for reuse
+    pub fn wait(mut self) -> Result<PageLockedMemory<T>> {
+        self.stream.sync()?;
+        Ok(self.mem.take().unwrap())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,6 +419,81 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(all(feature = "numa", target_os = "linux"))]
+    #[test]
+    fn new_numa() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = PageLockedMemory::<i32>::new_numa(&context, 1 << 20, 0);
+        assert_eq!(mem.memory_type(), MemoryType::PageLocked);
+        assert!(mem.iter().all(|&x| x == 0));
+        mem[0] = 1;
+        let mut dev = DeviceMemory::<i32>::zeros(&context, 1 << 20);
+        dev.copy_from(&mem);
+        assert_eq!(dev[0], 1);
+        Ok(())
+    }
+
+    #[test]
+    fn into_vec() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut mem = PageLockedMemory::<u32>::zeros(&context, 12);
+        for (i, v) in mem.iter_mut().enumerate() {
+            *v = i as u32;
+        }
+        let v = mem.into_vec();
+        assert_eq!(v, (0..12).collect::<Vec<u32>>());
+        assert_eq!(v.as_slice().memory_type(), MemoryType::Host);
+        Ok(())
+    }
+
+    #[test]
+    fn builder() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let builders = [
+            PinnedBuilder::new(12),
+            PinnedBuilder::new(12).portable(),
+            PinnedBuilder::new(12).device_map(),
+            PinnedBuilder::new(12).write_combined(),
+            PinnedBuilder::new(12)
+                .portable()
+                .device_map()
+                .write_combined(),
+        ];
+        for builder in builders.iter() {
+            let mut mem: PageLockedMemory<i32> = builder.build(&context);
+            assert_eq!(mem.memory_type(), MemoryType::PageLocked);
+            let flags = unsafe {
+                contexted_new!(&context, cuMemHostGetFlags, mem.head_addr_mut() as *mut _)
+            }?;
+            assert_eq!(flags & builder.flags(), builder.flags());
+            mem.set(3);
+            let mut dev = DeviceMemory::<i32>::zeros(&context, 12);
+            dev.copy_from(&mem);
+            assert_eq!(dev.as_slice(), &[3; 12]);
+        }
+        Ok(())
+    }
+
+    #[cfg(all(feature = "hugepages", target_os = "linux"))]
+    #[test]
+    fn new_hugepages() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        // Not a multiple of huge page size
+        let n = (1 << 20) + 3;
+        let mut mem = PageLockedMemory::<i32>::new_hugepages(&context, n);
+        assert_eq!(mem.memory_type(), MemoryType::PageLocked);
+        assert!(mem.iter().all(|&x| x == 0));
+        mem[n - 1] = 1;
+        let mut dev = DeviceMemory::<i32>::zeros(&context, n);
+        dev.copy_from(&mem);
+        assert_eq!(dev[n - 1], 1);
+        Ok(())
+    }
+
     #[should_panic(expected = "Zero-sized malloc is forbidden")]
     #[test]
     fn page_locked_new_zero() {
@@ -1,6 +1,7 @@
 use super::*;
 use crate::{error::Result, *};
 use cuda::*;
+use num_traits::ToPrimitive;
 use std::{
     ffi::c_void,
     ops::{Deref, DerefMut},
@@ -10,6 +11,12 @@ use std::{
 pub struct RegisteredMemory<'a, T> {
     ctx: Context,
     mem: &'a mut [T],
+    /// Number of elements in each row
+    width: usize,
+    /// Number of rows
+    height: usize,
+    /// Size of each row in bytes including padding
+    pitch: usize,
 }
 
 impl<T> Deref for RegisteredMemory<'_, T> {
@@ -34,27 +41,149 @@ impl<T> Drop for RegisteredMemory<'_, T> {
                 self.mem.as_mut_ptr() as *mut c_void
             )
         } {
-            log::error!("Failed to unregister memory: {:?}", e);
+            error::report_error("Failed to unregister memory", &e);
         }
     }
 }
 
 impl<'a, T: Scalar> RegisteredMemory<'a, T> {
     pub fn new(ctx: &Context, mem: &'a mut [T]) -> Self {
-        unsafe {
-            contexted_call!(
-                ctx,
-                cuMemHostRegister_v2,
-                mem.as_mut_ptr() as *mut c_void,
-                mem.len() * T::size_of(),
-                0
-            )
-        }
-        .expect("Failed to register host memory into CUDA memory system");
-        Self {
+        Self::try_new(ctx, mem).expect("Failed to register host memory into CUDA memory system")
+    }
+
+    /// Register `mem`, returning an error instead of panic
+    ///
+    /// Returns [AccelError::AlreadyRegistered] if `mem` overlaps with a memory already registered,
+    /// e.g. by another [RegisteredMemory] created through [RegisteredMemory::register_raw].
+    ///
+    /// [AccelError::AlreadyRegistered]: ../error/enum.AccelError.html#variant.AlreadyRegistered
+    /// [RegisteredMemory]: ./struct.RegisteredMemory.html
+    /// [RegisteredMemory::register_raw]: ./struct.RegisteredMemory.html#method.register_raw
+    pub fn try_new(ctx: &Context, mem: &'a mut [T]) -> Result<Self> {
+        unsafe { Self::try_register_raw(ctx, mem.as_mut_ptr(), mem.len(), 0) }
+    }
+
+    /// Register `len` elements starting at `ptr` allocated by an arbitrary allocator
+    ///
+    /// `flags` are passed to [cuMemHostRegister] as is, e.g. `CU_MEMHOSTREGISTER_PORTABLE`.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// use std::alloc::{alloc_zeroed, dealloc, Layout};
+    /// let layout = Layout::from_size_align(4096, 4096).unwrap();
+    /// let ptr = unsafe { alloc_zeroed(layout) } as *mut u32;
+    /// let mem = unsafe { RegisteredMemory::register_raw(&ctx, ptr, 1024, 0) };
+    /// assert_eq!(mem.as_slice(), &[0; 1024]);
+    /// drop(mem);
+    /// unsafe { dealloc(ptr as *mut u8, layout) };
+    /// ```
+    ///
+    /// Safety
+    /// ------
+    /// - `ptr` must be valid for reads and writes of `len` elements, and initialized
+    /// - The memory must not be accessed through any other pointer while the returned value exists,
+    ///   since it is accessible as `&mut [T]` for the lifetime `'a`
+    /// - The memory must not be deallocated before the returned value is dropped
+    ///
+    /// [cuMemHostRegister]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
+    pub unsafe fn register_raw(ctx: &Context, ptr: *mut T, len: usize, flags: u32) -> Self {
+        Self::try_register_raw(ctx, ptr, len, flags)
+            .expect("Failed to register host memory into CUDA memory system")
+    }
+
+    /// Same as [RegisteredMemory::register_raw], but returns an error instead of panic
+    ///
+    /// Safety
+    /// ------
+    /// Same as [RegisteredMemory::register_raw]
+    ///
+    /// [RegisteredMemory::register_raw]: ./struct.RegisteredMemory.html#method.register_raw
+    pub unsafe fn try_register_raw(
+        ctx: &Context,
+        ptr: *mut T,
+        len: usize,
+        flags: u32,
+    ) -> Result<Self> {
+        contexted_call!(
+            ctx,
+            cuMemHostRegister_v2,
+            ptr as *mut c_void,
+            len * T::size_of(),
+            flags
+        )?;
+        Ok(Self {
             ctx: ctx.clone(),
-            mem,
-        }
+            mem: std::slice::from_raw_parts_mut(ptr, len),
+            width: len,
+            height: 1,
+            pitch: len * T::size_of(),
+        })
+    }
+
+    /// Register a 2D host memory whose rows are padded to `pitch` bytes
+    ///
+    /// - `width` is the number of elements in each row, and `height` is the number of rows
+    /// - `pitch` is the distance between the heads of two adjacent rows in bytes
+    ///
+    /// Panic
+    /// ------
+    /// - if `pitch` is smaller than a row, i.e. `width * size_of::<T>()`
+    /// - if `mem` is too small to contain `height` rows
+    pub fn new_2d(
+        ctx: &Context,
+        mem: &'a mut [T],
+        width: usize,
+        height: usize,
+        pitch: usize,
+    ) -> Self {
+        assert!(
+            pitch >= width * T::size_of(),
+            "Pitch must be larger than the row size"
+        );
+        assert!(
+            height == 0 || mem.len() * T::size_of() >= pitch * (height - 1) + width * T::size_of(),
+            "Host memory is too small for given 2D shape"
+        );
+        let mut registered = Self::new(ctx, mem);
+        registered.width = width;
+        registered.height = height;
+        registered.pitch = pitch;
+        registered
+    }
+
+    /// Size of each row in bytes including padding
+    pub fn pitch(&self) -> usize {
+        self.pitch
+    }
+
+    /// Upload the 2D region into an array without repacking the padded rows
+    ///
+    /// Panic
+    /// ------
+    /// - if the 2D shape of this memory is different from the array
+    pub fn copy_to_array_2d(&self, array: &mut Array<T, Ix2>) {
+        let dim = *array.dim();
+        let width = dim.width * dim.num_channels().to_usize().unwrap();
+        assert_eq!(self.width, width, "Width mismatch");
+        assert_eq!(self.height, dim.height, "Height mismatch");
+        let param = CUDA_MEMCPY3D {
+            srcMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_HOST,
+            srcHost: self.mem.as_ptr() as *const c_void,
+            srcPitch: self.pitch,
+
+            dstMemoryType: CUmemorytype_enum::CU_MEMORYTYPE_ARRAY,
+            dstArray: array.array,
+
+            WidthInBytes: width * T::size_of(),
+            Height: dim.height,
+            Depth: 1,
+
+            ..Default::default()
+        };
+        unsafe { contexted_call!(&self.ctx, cuMemcpy3D_v2, &param) }
+            .expect("memcpy into array failed");
     }
 }
 
@@ -92,3 +221,100 @@ impl<T: Scalar> Continuous for RegisteredMemory<'_, T> {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_handler_on_drop() -> Result<()> {
+        use std::sync::{Arc, Mutex};
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let _guard = {
+            let errors = errors.clone();
+            crate::scoped_error_handler(move |e| errors.lock().unwrap().push(e.clone()))
+        };
+        let mut a = vec![0_i32; 12];
+        let ptr = a.as_mut_ptr() as *mut c_void;
+        let mem = RegisteredMemory::new(&context, &mut a);
+        // Unregister behind the wrapper to force an error in `Drop`
+        unsafe { contexted_call!(&context, cuMemHostUnregister, ptr) }?;
+        drop(mem);
+        // Errors of other tests running in parallel may also be captured
+        assert!(errors.lock().unwrap().iter().any(|e| matches!(
+            e,
+            error::AccelError::CUDAError {
+                api_name,
+                error: cudaError_enum::CUDA_ERROR_HOST_MEMORY_NOT_REGISTERED,
+                ..
+            } if api_name == "cuMemHostUnregister"
+        )));
+        Ok(())
+    }
+
+    #[test]
+    fn register_raw() -> Result<()> {
+        use std::alloc::{alloc_zeroed, dealloc, Layout};
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let n = 4096;
+        let layout = Layout::from_size_align(n * i32::size_of(), 4096).unwrap();
+        let ptr = unsafe { alloc_zeroed(layout) } as *mut i32;
+        assert!(!ptr.is_null());
+        {
+            let mut mem = unsafe { RegisteredMemory::register_raw(&context, ptr, n, 0) };
+            assert_eq!(mem.memory_type(), MemoryType::Host);
+            for (i, v) in mem.iter_mut().enumerate() {
+                *v = i as i32;
+            }
+            let mut dev = DeviceMemory::<i32>::zeros(&context, n);
+            dev.copy_from(&mem);
+            for i in 0..n {
+                assert_eq!(dev[i], i as i32);
+            }
+        }
+        unsafe { dealloc(ptr as *mut u8, layout) };
+        Ok(())
+    }
+
+    #[test]
+    fn register_twice() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut a = vec![0_u32; 1024];
+        let mut mem = RegisteredMemory::try_new(&context, &mut a)?;
+        let second =
+            unsafe { RegisteredMemory::try_register_raw(&context, mem.as_mut_ptr(), 1024, 0) };
+        assert_eq!(second.err(), Some(error::AccelError::AlreadyRegistered));
+        Ok(())
+    }
+
+    #[test]
+    fn copy_to_array_2d() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let width = 3;
+        let height = 2;
+        let pitch = 4; // one padding element in each row
+        let mut host = vec![0_u32; pitch * height];
+        for y in 0..height {
+            for x in 0..width {
+                host[y * pitch + x] = (y * width + x) as u32;
+            }
+            host[y * pitch + width] = 9999; // padding
+        }
+        let mem =
+            RegisteredMemory::new_2d(&context, &mut host, width, height, pitch * u32::size_of());
+        let mut array = Array::<u32, Ix2>::zeros(&context, (width, height).into());
+        mem.copy_to_array_2d(&mut array);
+
+        let mut dst = PageLockedMemory::<u32>::zeros(&context, width * height);
+        dst.copy_from(&array);
+        for i in 0..width * height {
+            assert_eq!(dst[i], i as u32);
+        }
+        Ok(())
+    }
+}
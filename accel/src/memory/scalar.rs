@@ -1,9 +1,19 @@
+pub use accel_derive::Scalar;
 pub use cuda::CUarray_format as ArrayFormatTag;
-use num_traits::Num;
+use num_traits::{Num, One, Zero};
+use std::ops::*;
 
 pub trait Scalar: Num + Copy {
     fn format() -> ArrayFormatTag;
 
+    /// Number of channels of [Scalar::format] in this type
+    ///
+    /// This is larger than one for vector types, e.g. [Float4],
+    /// and multiplied to the number of channels of the array descriptor.
+    fn channels() -> u32 {
+        1
+    }
+
     fn size_of() -> usize {
         std::mem::size_of::<Self>()
     }
@@ -52,3 +62,85 @@ impl_array_scalar!(i32, u32, CU_AD_FORMAT_SIGNED_INT32);
 // FIXME f16 is not supported yet
 // impl_array_scalar!(f16, u16, CU_AD_FORMAT_HALF);
 impl_array_scalar!(f32, u32, CU_AD_FORMAT_FLOAT);
+
+macro_rules! impl_vector_op {
+    ($vec:ident, $trait:ident, $method:ident, [$($field:ident),*]) => {
+        impl $trait for $vec {
+            type Output = Self;
+            fn $method(self, rhs: Self) -> Self {
+                $vec {
+                    $($field: $trait::$method(self.$field, rhs.$field)),*
+                }
+            }
+        }
+    };
+}
+
+macro_rules! impl_vector_scalar {
+    ($vec:ident, $scalar:ty, $format:ident, $align:literal, [$($field:ident),*]) => {
+        #[doc = concat!("Vector type compatible with `", stringify!($vec), "` in CUDA C")]
+        ///
+        /// Arithmetic operations are component-wise.
+        #[repr(C, align($align))]
+        #[derive(Debug, Clone, Copy, PartialEq, Default)]
+        pub struct $vec {
+            $(pub $field: $scalar),*
+        }
+
+        impl $vec {
+            pub fn new($($field: $scalar),*) -> Self {
+                $vec { $($field),* }
+            }
+
+            /// Set all components by `value`
+            pub fn splat(value: $scalar) -> Self {
+                $vec { $($field: value),* }
+            }
+        }
+
+        impl_vector_op!($vec, Add, add, [$($field),*]);
+        impl_vector_op!($vec, Sub, sub, [$($field),*]);
+        impl_vector_op!($vec, Mul, mul, [$($field),*]);
+        impl_vector_op!($vec, Div, div, [$($field),*]);
+        impl_vector_op!($vec, Rem, rem, [$($field),*]);
+
+        impl Zero for $vec {
+            fn zero() -> Self {
+                Self::splat(<$scalar>::zero())
+            }
+            fn is_zero(&self) -> bool {
+                $(self.$field.is_zero())&&*
+            }
+        }
+
+        impl One for $vec {
+            fn one() -> Self {
+                Self::splat(<$scalar>::one())
+            }
+        }
+
+        impl Num for $vec {
+            type FromStrRadixErr = <$scalar as Num>::FromStrRadixErr;
+            /// Parse a scalar and set it to all components
+            fn from_str_radix(s: &str, radix: u32) -> Result<Self, Self::FromStrRadixErr> {
+                <$scalar>::from_str_radix(s, radix).map(Self::splat)
+            }
+        }
+
+        impl Scalar for $vec {
+            fn format() -> ArrayFormatTag {
+                ArrayFormatTag::$format
+            }
+            fn channels() -> u32 {
+                [$(stringify!($field)),*].len() as u32
+            }
+        }
+    };
+}
+
+impl_vector_scalar!(Float2, f32, CU_AD_FORMAT_FLOAT, 8, [x, y]);
+impl_vector_scalar!(Float4, f32, CU_AD_FORMAT_FLOAT, 16, [x, y, z, w]);
+impl_vector_scalar!(Int2, i32, CU_AD_FORMAT_SIGNED_INT32, 8, [x, y]);
+impl_vector_scalar!(Int4, i32, CU_AD_FORMAT_SIGNED_INT32, 16, [x, y, z, w]);
+impl_vector_scalar!(UInt2, u32, CU_AD_FORMAT_UNSIGNED_INT32, 8, [x, y]);
+impl_vector_scalar!(UInt4, u32, CU_AD_FORMAT_UNSIGNED_INT32, 16, [x, y, z, w]);
@@ -23,9 +23,10 @@ fn memory_type<T>(ptr: *const T) -> MemoryType {
         Ok(CUmemorytype_enum::CU_MEMORYTYPE_HOST) => MemoryType::PageLocked,
         Ok(CUmemorytype_enum::CU_MEMORYTYPE_DEVICE) => MemoryType::Device,
         Ok(CUmemorytype_enum::CU_MEMORYTYPE_ARRAY) => MemoryType::Array,
-        Ok(CUmemorytype_enum::CU_MEMORYTYPE_UNIFIED) => {
-            unreachable!("CU_POINTER_ATTRIBUTE_MEMORY_TYPE never be UNIFED")
-        }
+        // Although the driver API document does not say it, some drivers return UNIFIED
+        // for managed memory. Managed memory is regarded as device memory,
+        // since `DeviceMemory` is allocated by `cuMemAllocManaged`.
+        Ok(CUmemorytype_enum::CU_MEMORYTYPE_UNIFIED) => MemoryType::Device,
         Err(_) => {
             // unmanaged by CUDA memory system, i.e. host memory
             MemoryType::Host
@@ -33,12 +34,132 @@ fn memory_type<T>(ptr: *const T) -> MemoryType {
     }
 }
 
+/// Get the ordinal of the device where the memory pointed by `ptr` exists
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+/// assert_eq!(device_ordinal(mem.head_addr()).unwrap(), 0);
+/// ```
+///
+/// Returns an error if the pointer is not managed by CUDA, e.g. usual host memory.
+pub fn device_ordinal<T>(ptr: *const T) -> error::Result<i32> {
+    get_attr(
+        ptr,
+        CUpointer_attribute::CU_POINTER_ATTRIBUTE_DEVICE_ORDINAL,
+    )
+}
+
+/// Check if the memory pointed by `ptr` is managed memory allocated by `cuMemAllocManaged`
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+/// assert!(is_managed(mem.head_addr()));
+/// let host = PageLockedMemory::<i32>::zeros(&ctx, 12);
+/// assert!(!is_managed(host.head_addr()));
+/// ```
+///
+/// Returns `false` if the pointer is not managed by CUDA, e.g. usual host memory.
+pub fn is_managed<T>(ptr: *const T) -> bool {
+    get_attr::<_, u32>(ptr, CUpointer_attribute::CU_POINTER_ATTRIBUTE_IS_MANAGED)
+        .map(|managed| managed != 0)
+        .unwrap_or(false)
+}
+
 fn get_context<T>(ptr: *const T) -> Option<ContextRef> {
     let ptr =
         get_attr::<_, CUcontext>(ptr, CUpointer_attribute::CU_POINTER_ATTRIBUTE_CONTEXT).ok()?;
     Some(ContextRef::from_ptr(ptr))
 }
 
+/// Body of [Memory::accessible_from] for the memory of `memory_type` pointed by `ptr`
+///
+/// [Memory::accessible_from]: ./trait.Memory.html#method.accessible_from
+pub(crate) fn accessible_from<T>(ptr: *const T, memory_type: MemoryType, ctx: &Context) -> bool {
+    match memory_type {
+        MemoryType::Host | MemoryType::Array => false,
+        MemoryType::PageLocked => ctx
+            .device()
+            .and_then(|device| device.unified_addressing())
+            .unwrap_or(false),
+        MemoryType::Device => {
+            if is_managed(ptr) {
+                return true;
+            }
+            let owner = match get_context(ptr) {
+                Some(owner) => owner,
+                None => return false,
+            };
+            if owner == **ctx {
+                return true;
+            }
+            match (owner.device(), ctx.device()) {
+                (Ok(src), Ok(dest)) => dest.can_access_peer(&src).unwrap_or(false),
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Push the context owning the memory pointed by `ptr`, and pop it when the guard is dropped
+///
+/// This is useful to call CUDA APIs on a memory whose context is not current,
+/// e.g. a memory allocated in another context.
+/// Returns `None` if the pointer is not managed by CUDA, e.g. usual host memory,
+/// and an error if the context cannot be pushed.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+/// assert!(guard_pointer_context(mem.head_addr()).unwrap().is_some());
+/// let host = vec![0_i32; 12];
+/// assert!(guard_pointer_context(host.as_ptr()).unwrap().is_none());
+/// ```
+pub fn guard_pointer_context<T>(ptr: *const T) -> error::Result<Option<ContextGuard>> {
+    match get_context(ptr) {
+        Some(ctx) => Ok(Some(ctx.guard()?)),
+        None => Ok(None),
+    }
+}
+
+/// Warn a copy between device and pageable host memory
+///
+/// Such a copy is staged through a driver-managed page-locked buffer,
+/// and blocks the host thread until the transfer completes.
+/// Copies between pageable and page-locked host memories are host-to-host, and not warned.
+#[cfg(feature = "warn-implicit-sync")]
+fn warn_implicit_sync(dest: MemoryType, src: MemoryType) {
+    use MemoryType::{Device, Host};
+    if matches!((dest, src), (Host, Device) | (Device, Host)) {
+        log::warn!(
+            "Synchronous copy from {:?} to {:?} memory through pageable host memory. \
+             Use PageLockedMemory or RegisteredMemory to avoid implicit synchronization",
+            src,
+            dest
+        );
+    }
+}
+
+/// Copy between page-locked host memories larger than this size in bytes uses `cuMemcpy`
+///
+/// The driver copy is faster for large pinned buffers, while `copy_from_slice` avoids
+/// the overhead of calling the driver for small ones.
+const PINNED_MEMCPY_THRESHOLD: usize = 1 << 16;
+
+/// Check if both are page-locked host memory and the copy is small enough to be done on the host
+fn is_small_host_copy<T: Scalar>(dest: &[T], src: &[T]) -> bool {
+    dest.len() * T::size_of() <= PINNED_MEMCPY_THRESHOLD
+        && dest.memory_type() == MemoryType::PageLocked
+        && src.memory_type() == MemoryType::PageLocked
+}
+
 impl<T: Scalar> Memory for [T] {
     type Elem = T;
     fn head_addr(&self) -> *const T {
@@ -59,22 +180,31 @@ impl<T: Scalar> Memory for [T] {
 }
 
 impl<T: Scalar> Memcpy<[T]> for [T] {
-    fn copy_from(&mut self, src: &[T]) {
-        assert_ne!(self.head_addr(), src.head_addr());
-        assert_eq!(self.num_elem(), src.num_elem());
-        if let Some(ctx) = get_context(self.head_addr()).or_else(|| get_context(src.head_addr())) {
+    fn try_copy_from(&mut self, src: &[T]) -> error::Result<()> {
+        check_distinct(self.head_addr(), src.head_addr())?;
+        check_size(self.num_elem(), src.num_elem())?;
+        if is_small_host_copy(self, src) {
+            self.copy_from_slice(src);
+            return Ok(());
+        }
+        let guard = match guard_pointer_context(self.head_addr())? {
+            Some(g) => Some(g),
+            None => guard_pointer_context(src.head_addr())?,
+        };
+        if let Some(_g) = guard {
+            #[cfg(feature = "warn-implicit-sync")]
+            warn_implicit_sync(self.memory_type(), src.memory_type());
             unsafe {
-                contexted_call!(
-                    &ctx,
+                ffi_call!(
                     cuMemcpy,
                     self.head_addr_mut() as CUdeviceptr,
                     src.as_ptr() as CUdeviceptr,
                     self.num_elem() * T::size_of()
                 )
             }
-            .unwrap()
         } else {
             self.copy_from_slice(src);
+            Ok(())
         }
     }
 }
@@ -82,13 +212,13 @@ impl<T: Scalar> Memcpy<[T]> for [T] {
 macro_rules! impl_memcpy_slice {
     ($t:path) => {
         impl<T: Scalar> Memcpy<[T]> for $t {
-            fn copy_from(&mut self, src: &[T]) {
-                self.as_mut_slice().copy_from(src);
+            fn try_copy_from(&mut self, src: &[T]) -> error::Result<()> {
+                self.as_mut_slice().try_copy_from(src)
             }
         }
         impl<T: Scalar> Memcpy<$t> for [T] {
-            fn copy_from(&mut self, src: &$t) {
-                self.copy_from(src.as_slice());
+            fn try_copy_from(&mut self, src: &$t) -> error::Result<()> {
+                self.try_copy_from(src.as_slice())
             }
         }
     };
@@ -97,12 +227,13 @@ macro_rules! impl_memcpy_slice {
 impl_memcpy_slice!(DeviceMemory::<T>);
 impl_memcpy_slice!(PageLockedMemory::<T>);
 impl_memcpy_slice!(RegisteredMemory::<'_, T>);
+impl_memcpy_slice!(ArenaSlice::<'_, T>);
 
 macro_rules! impl_memcpy {
     ($from:path, $to:path) => {
         impl<T: Scalar> Memcpy<$from> for $to {
-            fn copy_from(&mut self, src: &$from) {
-                self.as_mut_slice().copy_from(src.as_slice());
+            fn try_copy_from(&mut self, src: &$from) -> error::Result<()> {
+                self.as_mut_slice().try_copy_from(src.as_slice())
             }
         }
     };
@@ -117,6 +248,13 @@ impl_memcpy!(PageLockedMemory::<T>, PageLockedMemory::<T>);
 impl_memcpy!(RegisteredMemory::<'_, T>, DeviceMemory::<T>);
 impl_memcpy!(RegisteredMemory::<'_, T>, RegisteredMemory::<'_, T>);
 impl_memcpy!(RegisteredMemory::<'_, T>, PageLockedMemory::<T>);
+impl_memcpy!(ArenaSlice::<'_, T>, ArenaSlice::<'_, T>);
+impl_memcpy!(ArenaSlice::<'_, T>, DeviceMemory::<T>);
+impl_memcpy!(ArenaSlice::<'_, T>, PageLockedMemory::<T>);
+impl_memcpy!(ArenaSlice::<'_, T>, RegisteredMemory::<'_, T>);
+impl_memcpy!(DeviceMemory::<T>, ArenaSlice::<'_, T>);
+impl_memcpy!(PageLockedMemory::<T>, ArenaSlice::<'_, T>);
+impl_memcpy!(RegisteredMemory::<'_, T>, ArenaSlice::<'_, T>);
 
 impl<T: Scalar> Continuous for [T] {
     fn as_slice(&self) -> &[Self::Elem] {
@@ -128,6 +266,116 @@ impl<T: Scalar> Continuous for [T] {
     }
 }
 
+impl<T: Scalar, const N: usize> Memory for [T; N] {
+    type Elem = T;
+    fn head_addr(&self) -> *const T {
+        self.as_ptr()
+    }
+
+    fn head_addr_mut(&mut self) -> *mut T {
+        self.as_mut_ptr()
+    }
+
+    fn num_elem(&self) -> usize {
+        N
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        memory_type(self.as_ptr())
+    }
+}
+
+impl<T: Scalar, const N: usize> Continuous for [T; N] {
+    fn as_slice(&self) -> &[Self::Elem] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [Self::Elem] {
+        self
+    }
+}
+
+macro_rules! impl_memcpy_fixed_size_array {
+    ($t:ty) => {
+        impl<T: Scalar, const N: usize> Memcpy<$t> for [T; N] {
+            fn try_copy_from(&mut self, src: &$t) -> error::Result<()> {
+                self.as_mut_slice().try_copy_from(src.as_slice())
+            }
+        }
+        impl<T: Scalar, const N: usize> Memcpy<[T; N]> for $t {
+            fn try_copy_from(&mut self, src: &[T; N]) -> error::Result<()> {
+                self.as_mut_slice().try_copy_from(src.as_slice())
+            }
+        }
+    };
+}
+
+impl_memcpy_fixed_size_array!(DeviceMemory<T>);
+impl_memcpy_fixed_size_array!(PageLockedMemory<T>);
+impl_memcpy_fixed_size_array!(RegisteredMemory<'_, T>);
+impl_memcpy_fixed_size_array!(ArenaSlice<'_, T>);
+
+impl<T: Scalar, const N: usize> Memcpy<[T]> for [T; N] {
+    fn try_copy_from(&mut self, src: &[T]) -> error::Result<()> {
+        self.as_mut_slice().try_copy_from(src)
+    }
+}
+
+impl<T: Scalar, const N: usize> Memcpy<[T; N]> for [T] {
+    fn try_copy_from(&mut self, src: &[T; N]) -> error::Result<()> {
+        self.try_copy_from(src.as_slice())
+    }
+}
+
+impl<T: Scalar, const N: usize> Memcpy<[T; N]> for [T; N] {
+    fn try_copy_from(&mut self, src: &[T; N]) -> error::Result<()> {
+        self.as_mut_slice().try_copy_from(src.as_slice())
+    }
+}
+
+impl<T: Scalar> Memory for Vec<T> {
+    type Elem = T;
+    fn head_addr(&self) -> *const T {
+        self.as_ptr()
+    }
+
+    fn head_addr_mut(&mut self) -> *mut T {
+        self.as_mut_ptr()
+    }
+
+    fn num_elem(&self) -> usize {
+        self.len()
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        memory_type(self.as_ptr())
+    }
+}
+
+impl<T: Scalar> Continuous for Vec<T> {
+    fn as_slice(&self) -> &[Self::Elem] {
+        self
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [Self::Elem] {
+        self
+    }
+}
+
+// `Vec<T>` implements all `Memcpy` as well as `[T]`.
+// Otherwise, `vec.copy_from(&mem)` does not auto-deref into `[T]` once `Vec<T>` implements any `Memcpy`.
+impl_memcpy_slice!(Vec::<T>);
+impl_memcpy!(Vec::<T>, Vec::<T>);
+impl_memcpy!(Vec::<T>, DeviceMemory::<T>);
+impl_memcpy!(Vec::<T>, PageLockedMemory::<T>);
+impl_memcpy!(Vec::<T>, RegisteredMemory::<'_, T>);
+impl_memcpy!(Vec::<T>, ArenaSlice::<'_, T>);
+impl_memcpy!(DeviceMemory::<T>, Vec::<T>);
+impl_memcpy!(PageLockedMemory::<T>, Vec::<T>);
+impl_memcpy!(RegisteredMemory::<'_, T>, Vec::<T>);
+impl_memcpy!(ArenaSlice::<'_, T>, Vec::<T>);
+impl_memcpy_fixed_size_array!(Vec<T>);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -150,6 +398,251 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn try_copy_from_same_memory() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let mut mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+        let ptr = mem.as_mut_ptr();
+        // Only the addresses are compared before returning the error
+        let (dest, src) = unsafe {
+            (
+                std::slice::from_raw_parts_mut(ptr, 12),
+                std::slice::from_raw_parts(ptr, 12),
+            )
+        };
+        assert!(matches!(
+            dest.try_copy_from(src),
+            Err(error::AccelError::InvalidValue { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn copy_from_chunked_cancel() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let src = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+        let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+        let cancel = AtomicBool::new(true); // cancel just after the first chunk
+        let bytes = dest.copy_from_chunked(&src, 5, &cancel);
+        assert_eq!(bytes, 5 * i32::size_of());
+        assert!(dest[..5].iter().all(|&x| x == 1));
+        assert!(dest[5..].iter().all(|&x| x == 0));
+        Ok(())
+    }
+
+    #[test]
+    fn copy_from_chunked_default() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let src = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+        let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+        // Chunk of the recommended length covers all elements at once
+        let cancel = AtomicBool::new(true);
+        let bytes = dest.copy_from_chunked(&src, 0, &cancel);
+        assert_eq!(bytes, 12 * i32::size_of());
+        assert!(dest.iter().all(|&x| x == 1));
+        Ok(())
+    }
+
+    #[test]
+    fn copy_from_chunked_with_progress() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let src = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+        let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+        let cancel = AtomicBool::new(false);
+        let mut reports = Vec::new();
+        let bytes = dest.copy_from_chunked_with_progress(&src, 5, &cancel, |done, total| {
+            reports.push((done, total))
+        });
+        let total = 12 * i32::size_of();
+        assert_eq!(bytes, total);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports.last(), Some(&(total, total)));
+        assert_eq!(dest.as_slice(), src.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn copy_prefix_from() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let src = PageLockedMemory::<i32>::from_elem(&ctx, 5, 1);
+        let mut dest = DeviceMemory::<i32>::zeros(&ctx, 10);
+        assert_eq!(dest.copy_prefix_from(&src), 5);
+        assert!(dest[..5].iter().all(|&x| x == 1));
+        assert!(dest[5..].iter().all(|&x| x == 0));
+
+        // shorter destination
+        let mut dest = vec![0_i32; 3];
+        assert_eq!(dest.as_mut_slice().copy_prefix_from(&src), 3);
+        assert_eq!(dest, vec![1; 3]);
+        Ok(())
+    }
+
+    #[test]
+    fn memcpy_fixed_size_array() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let src = [1.0_f32, 2.0, 3.0, 4.0];
+        assert_eq!(src.memory_type(), MemoryType::Host);
+        assert_eq!(src.num_elem(), 4);
+        let mut dev = DeviceMemory::<f32>::zeros(&ctx, 4);
+        dev.copy_from(&src);
+        assert_eq!(dev.as_slice(), &src);
+        let mut dst = [0.0_f32; 4];
+        dst.copy_from(&dev);
+        assert_eq!(dst, src);
+        Ok(())
+    }
+
+    #[test]
+    fn memory_type_managed() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let ptr: CUdeviceptr = unsafe {
+            contexted_new!(
+                &ctx,
+                cuMemAllocManaged,
+                12 * u32::size_of(),
+                CUmemAttach_flags_enum::CU_MEM_ATTACH_GLOBAL as u32
+            )
+        }?;
+        assert_eq!(memory_type(ptr as *const u32), MemoryType::Device);
+        unsafe { contexted_call!(&ctx, cuMemFree_v2, ptr) }?;
+        Ok(())
+    }
+
+    #[test]
+    fn copy_managed_device() -> error::Result<()> {
+        // `DeviceMemory` is allocated by `cuMemAllocManaged`, and must be copied by `cuMemcpy`
+        // using its context instead of regarded as host memory
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let mut managed = DeviceMemory::<i32>::from_elem(&ctx, 12, 1);
+        let mut dev = DeviceMemory::<i32>::zeros(&ctx, 12);
+        assert_eq!(managed.memory_type(), MemoryType::Device);
+        assert_eq!(*ctx, get_context(managed.head_addr()).unwrap());
+        assert_eq!(*ctx, get_context(dev.head_addr()).unwrap());
+
+        dev.copy_from(&managed);
+        assert_eq!(dev.to_vec(), vec![1; 12]);
+
+        dev.set(2);
+        managed.copy_from(&dev);
+        assert_eq!(managed.to_vec(), vec![2; 12]);
+        Ok(())
+    }
+
+    #[test]
+    fn context_ref_device() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let a = DeviceMemory::<i32>::zeros(&ctx, 12);
+        let ctx_ref = get_context(a.head_addr()).unwrap();
+        assert_eq!(ctx_ref.device()?, device);
+        assert_eq!(ctx.device()?, device);
+        Ok(())
+    }
+
+    #[test]
+    fn is_managed() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mem = DeviceMemory::<i32>::zeros(&context, 12);
+        assert!(super::is_managed(mem.head_addr()));
+        let host = PageLockedMemory::<i32>::zeros(&context, 12);
+        assert!(!super::is_managed(host.head_addr()));
+        let host = [0_i32; 12];
+        assert!(!super::is_managed(host.as_ptr()));
+        Ok(())
+    }
+
+    #[test]
+    fn memcpy_pinned() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        for &n in &[12, 4 * PINNED_MEMCPY_THRESHOLD] {
+            let mut src = PageLockedMemory::<u32>::zeros(&context, n);
+            src.iter_mut().enumerate().for_each(|(i, v)| *v = i as u32);
+            let mut dest = PageLockedMemory::<u32>::zeros(&context, n);
+            dest.as_mut_slice().copy_from(src.as_slice());
+            assert_eq!(dest.as_slice(), src.as_slice());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn accessible_from() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let foreign = device.create_context();
+
+        let mem = DeviceMemory::<i32>::zeros(&ctx, 12);
+        assert!(mem.accessible_from(&ctx));
+        assert!(mem.accessible_from(&foreign));
+
+        let unified = device.unified_addressing()?;
+        let page_locked = PageLockedMemory::<i32>::zeros(&ctx, 12);
+        assert_eq!(page_locked.accessible_from(&ctx), unified);
+        assert_eq!(page_locked.accessible_from(&foreign), unified);
+
+        let mut host = vec![0_i32; 12];
+        assert!(!host.as_slice().accessible_from(&ctx));
+        assert!(!host.as_slice().accessible_from(&foreign));
+
+        let registered = RegisteredMemory::new(&ctx, &mut host);
+        assert_eq!(registered.accessible_from(&ctx), unified);
+        assert_eq!(registered.accessible_from(&foreign), unified);
+        drop(registered);
+
+        let array = Array::<i32, Ix1>::zeros(&ctx, 12.into());
+        assert!(!array.accessible_from(&ctx));
+        assert!(!array.accessible_from(&foreign));
+        Ok(())
+    }
+
+    #[test]
+    fn guard_pointer_context() -> error::Result<()> {
+        let device = Device::nth(0)?;
+        let ctx1 = device.create_context();
+        let ctx2 = device.create_context();
+        let current = || unsafe { ffi_new!(cuCtxGetCurrent) }.map(ContextRef::from_ptr);
+        let mem = DeviceMemory::<i32>::zeros(&ctx1, 12);
+
+        let _g2 = ctx2.guard()?;
+        assert_eq!(current()?, *ctx2);
+        let g1 = super::guard_pointer_context(mem.head_addr())?.unwrap();
+        assert_eq!(current()?, *ctx1);
+        drop(g1);
+        assert_eq!(current()?, *ctx2);
+        Ok(())
+    }
+
+    #[cfg(feature = "warn-implicit-sync")]
+    #[test]
+    fn warn_implicit_sync() -> error::Result<()> {
+        drop(error::captured_warnings());
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let host = vec![1_i32; 12];
+        let mut dev = DeviceMemory::<i32>::zeros(&ctx, 12);
+        dev.copy_from(&host);
+        // Host-to-host copy through page-locked memory does not stall
+        let pinned = PageLockedMemory::<i32>::from_elem(&ctx, 12, 2);
+        let mut host = vec![0_i32; 12];
+        host.copy_from(&pinned);
+        // Other tests running in parallel may also be captured
+        let warnings = error::captured_warnings();
+        assert!(warnings.iter().any(|w| w.contains("from Host to Device")));
+        assert!(!warnings
+            .iter()
+            .any(|w| w.contains("from PageLocked to Host")));
+        Ok(())
+    }
+
     #[test]
     fn restore_context() -> error::Result<()> {
         let device = Device::nth(0)?;
@@ -0,0 +1,135 @@
+//! CUDA [Texture] Object
+//!
+//! [Texture]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__TEXOBJECT.html#group__CUDA__TEXOBJECT
+
+use super::*;
+use crate::{contexted_call, contexted_new, device::Contexted, error::Result, *};
+use cuda::*;
+
+pub use cuda::CUDA_TEXTURE_DESC as TextureDescriptor;
+
+/// Texture object backed by an [Array](./struct.Array.html)
+///
+/// The texture borrows its backing array, i.e. the array cannot be dropped while the texture is alive:
+///
+/// ```compile_fail
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let array = Array::<f32, Ix1>::zeros(&ctx, 10.into());
+/// let texture = array.create_texture(&TextureDescriptor::default());
+/// drop(array); // compile fail
+/// drop(texture);
+/// ```
+#[derive(Debug)]
+pub struct Texture<'array, T, Dim> {
+    texture: CUtexObject,
+    array: &'array Array<T, Dim>,
+}
+
+impl<T, Dim> Drop for Texture<'_, T, Dim> {
+    fn drop(&mut self) {
+        if let Err(e) = unsafe { contexted_call!(self.array, cuTexObjectDestroy, self.texture) } {
+            error::report_error("Failed to destroy texture object", &e);
+        }
+    }
+}
+
+impl<T, Dim> Contexted for Texture<'_, T, Dim> {
+    fn sync(&self) -> Result<()> {
+        self.array.sync()
+    }
+
+    fn version(&self) -> Result<u32> {
+        self.array.version()
+    }
+
+    fn guard(&self) -> Result<ContextGuard> {
+        self.array.guard()
+    }
+}
+
+impl<'array, T: Scalar, Dim: Dimension> Texture<'array, T, Dim> {
+    /// Create a new texture object reading `array`
+    pub fn new(array: &'array Array<T, Dim>, desc: &TextureDescriptor) -> Self {
+        let resource = CUDA_RESOURCE_DESC {
+            resType: CUresourcetype::CU_RESOURCE_TYPE_ARRAY,
+            res: CUDA_RESOURCE_DESC_st__bindgen_ty_1 {
+                array: CUDA_RESOURCE_DESC_st__bindgen_ty_1__bindgen_ty_1 {
+                    hArray: array.array,
+                },
+            },
+            flags: 0,
+        };
+        let texture =
+            unsafe { contexted_new!(array, cuTexObjectCreate, &resource, desc, std::ptr::null()) }
+                .expect("Failed to create texture object");
+        Texture { texture, array }
+    }
+
+    /// Raw handle of the texture object to be passed into kernels
+    pub fn handle(&self) -> CUtexObject {
+        self.texture
+    }
+
+    /// Backing array
+    pub fn array(&self) -> &'array Array<T, Dim> {
+        self.array
+    }
+
+    /// Copy the content of the backing array into a linear memory
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let array = Array::<f32, Ix1>::from_elem(&ctx, 10.into(), 1.0);
+    /// let texture = array.create_texture(&TextureDescriptor::default());
+    /// let mut dest = vec![0.0; 10];
+    /// texture.copy_to(&mut dest);
+    /// assert_eq!(dest, vec![1.0; 10]);
+    /// ```
+    ///
+    /// Panic
+    /// -----
+    /// - if the number of elements of `dest` is different from the backing array
+    pub fn copy_to(&self, dest: &mut [T]) {
+        dest.copy_from(self.array);
+    }
+}
+
+impl<T: Scalar, Dim: Dimension> Array<T, Dim> {
+    /// Create a texture object reading this array
+    pub fn create_texture(&self, desc: &TextureDescriptor) -> Texture<'_, T, Dim> {
+        Texture::new(self, desc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn create_texture() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let array = Array::<f32, Ix2>::zeros(&context, (4, 4).into());
+        let texture = array.create_texture(&TextureDescriptor::default());
+        assert_ne!(texture.handle(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_to() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let mut array = Array::<i32, Ix2>::zeros(&context, (4, 4).into());
+        let src: Vec<i32> = (0..16).collect();
+        array.copy_from(src.as_slice());
+        let texture = array.create_texture(&TextureDescriptor::default());
+        let mut dest = PageLockedMemory::<i32>::zeros(&context, 16);
+        texture.copy_to(&mut dest);
+        assert_eq!(dest.as_slice(), src.as_slice());
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Low-level virtual memory management (VMM) API
+//!
+//! CUDA 10.2 introduces APIs to reserve a virtual address range by [cuMemAddressReserve],
+//! and to map physical memory created by [cuMemCreate] into it by [cuMemMap].
+//! Sizes and addresses used in these APIs must be aligned to the allocation granularity.
+//!
+//! [cuMemAddressReserve]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+//! [cuMemCreate]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+//! [cuMemMap]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+
+use crate::{error::Result, *};
+use cuda::*;
+use std::{marker::PhantomData, mem::MaybeUninit, ptr::addr_of_mut};
+
+/// Properties of pinned physical memory on `device` without any exportable handle
+///
+/// This is kept as `MaybeUninit` since zero `requestedHandleTypes`, i.e. no handle is requested,
+/// is not a valid value of the Rust enum generated by bindgen.
+pub(crate) fn allocation_prop(device: &Device) -> MaybeUninit<CUmemAllocationProp> {
+    let mut prop = MaybeUninit::<CUmemAllocationProp>::zeroed();
+    let p = prop.as_mut_ptr();
+    unsafe {
+        addr_of_mut!((*p).type_).write(CUmemAllocationType::CU_MEM_ALLOCATION_TYPE_PINNED);
+        addr_of_mut!((*p).location).write(CUmemLocation {
+            type_: CUmemLocationType::CU_MEM_LOCATION_TYPE_DEVICE,
+            id: device.device,
+        });
+    }
+    prop
+}
+
+/// Minimum granularity of physical memory on `device` in bytes by [cuMemGetAllocationGranularity]
+///
+/// ```
+/// # use accel::*;
+/// let device = Device::nth(0).unwrap();
+/// let granularity = virtual_mem::min_granularity(&device).unwrap();
+/// assert!(granularity.is_power_of_two());
+/// ```
+///
+/// [cuMemGetAllocationGranularity]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+pub fn min_granularity(device: &Device) -> Result<usize> {
+    let prop = allocation_prop(device);
+    let mut granularity = 0;
+    unsafe {
+        ffi_call!(
+            cuMemGetAllocationGranularity,
+            &mut granularity as *mut usize,
+            prop.as_ptr(),
+            CUmemAllocationGranularity_flags::CU_MEM_ALLOC_GRANULARITY_MINIMUM
+        )
+    }?;
+    Ok(granularity)
+}
+
+/// Device memory growing in place within a reserved virtual address range
+///
+/// A virtual address range for up to `max_len` elements is reserved by [cuMemAddressReserve] at creation,
+/// and physical memory is created by [cuMemCreate] and mapped by [cuMemMap] at the tail of the range
+/// when the memory grows. Since the address does not change, elements are never copied to grow.
+/// This is useful to collect results of unknown size.
+///
+/// The memory is not accessible from the host, and read by [GrowableDeviceMemory::to_vec].
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let mut mem = virtual_mem::GrowableDeviceMemory::<i32>::new(&ctx, 1 << 20).unwrap();
+/// mem.push_from(&[1, 2, 3]).unwrap();
+/// mem.push_from(&[4, 5]).unwrap();
+/// assert_eq!(mem.len(), 5);
+/// assert_eq!(mem.to_vec().unwrap(), vec![1, 2, 3, 4, 5]);
+/// ```
+///
+/// [cuMemAddressReserve]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+/// [cuMemCreate]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+/// [cuMemMap]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__VA.html
+/// [GrowableDeviceMemory::to_vec]: #method.to_vec
+#[derive(Contexted)]
+pub struct GrowableDeviceMemory<T> {
+    /// Head of the reserved address range
+    ptr: CUdeviceptr,
+    /// Size of the reserved address range in bytes
+    reserved: usize,
+    /// Physical memories mapped in order from the head of the range, and their sizes in bytes
+    handles: Vec<(CUmemGenericAllocationHandle, usize)>,
+    /// Size of the mapped range in bytes
+    committed: usize,
+    /// Number of elements pushed
+    len: usize,
+    granularity: usize,
+    device: Device,
+    context: Context,
+    phantom: PhantomData<T>,
+}
+
+impl<T> Drop for GrowableDeviceMemory<T> {
+    fn drop(&mut self) {
+        let mut head = self.ptr;
+        for &(handle, size) in &self.handles {
+            if let Err(e) = unsafe { contexted_call!(self, cuMemUnmap, head, size) } {
+                error::report_error("Failed to unmap virtual memory", &e);
+            }
+            if let Err(e) = unsafe { contexted_call!(self, cuMemRelease, handle) } {
+                error::report_error("Failed to release physical memory", &e);
+            }
+            head += size as CUdeviceptr;
+        }
+        if let Err(e) = unsafe { contexted_call!(self, cuMemAddressFree, self.ptr, self.reserved) }
+        {
+            error::report_error("Failed to free virtual address range", &e);
+        }
+    }
+}
+
+impl<T: Scalar> GrowableDeviceMemory<T> {
+    /// Reserve a virtual address range for up to `max_len` elements without physical memory
+    ///
+    /// Panic
+    /// -----
+    /// - if `max_len` is zero
+    pub fn new(context: &Context, max_len: usize) -> Result<Self> {
+        assert!(max_len > 0, "Zero-sized reservation is forbidden");
+        let device = context.device()?;
+        let granularity = min_granularity(&device)?;
+        let reserved = round_up(max_len * T::size_of(), granularity);
+        let ptr = unsafe { contexted_new!(context, cuMemAddressReserve, reserved, 0, 0, 0) }?;
+        Ok(GrowableDeviceMemory {
+            ptr,
+            reserved,
+            handles: Vec::new(),
+            committed: 0,
+            len: 0,
+            granularity,
+            device,
+            context: context.clone(),
+            phantom: PhantomData,
+        })
+    }
+
+    /// Number of elements pushed
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of elements which can be stored without mapping new physical memory
+    pub fn capacity(&self) -> usize {
+        self.committed / T::size_of()
+    }
+
+    /// Maximum number of elements in the reserved address range
+    pub fn max_len(&self) -> usize {
+        self.reserved / T::size_of()
+    }
+
+    /// Map physical memory to store at least `additional` more elements
+    ///
+    /// The mapped size is at least doubled to amortize the cost of mapping,
+    /// and rounded up to the granularity. Elements already pushed are not moved.
+    ///
+    /// Panic
+    /// -----
+    /// - if `len() + additional` exceeds [GrowableDeviceMemory::max_len]
+    ///
+    /// [GrowableDeviceMemory::max_len]: #method.max_len
+    pub fn reserve(&mut self, additional: usize) -> Result<()> {
+        let required = (self.len + additional) * T::size_of();
+        assert!(
+            required <= self.reserved,
+            "Exceeds the reserved address range"
+        );
+        if required <= self.committed {
+            return Ok(());
+        }
+        let target =
+            round_up(required.max(2 * self.committed), self.granularity).min(self.reserved);
+        let size = target - self.committed;
+        let head = self.ptr + self.committed as CUdeviceptr;
+        let prop = allocation_prop(&self.device);
+        let handle = unsafe { contexted_new!(self, cuMemCreate, size, prop.as_ptr(), 0) }?;
+        if let Err(e) = unsafe { contexted_call!(self, cuMemMap, head, size, 0, handle, 0) } {
+            if let Err(e) = unsafe { contexted_call!(self, cuMemRelease, handle) } {
+                error::report_error("Failed to release physical memory", &e);
+            }
+            return Err(e);
+        }
+        // Register before setting access, so that `Drop` unmaps and releases it even if failed
+        self.handles.push((handle, size));
+        self.committed = target;
+        let access = CUmemAccessDesc {
+            location: CUmemLocation {
+                type_: CUmemLocationType::CU_MEM_LOCATION_TYPE_DEVICE,
+                id: self.device.device,
+            },
+            flags: CUmemAccess_flags::CU_MEM_ACCESS_FLAGS_PROT_READWRITE,
+        };
+        unsafe { contexted_call!(self, cuMemSetAccess, head, size, &access, 1) }?;
+        Ok(())
+    }
+
+    /// Append elements in `src` at the tail, growing the memory if needed
+    ///
+    /// `src` may be any memory in the unified address space, e.g. host or device memory.
+    ///
+    /// Panic
+    /// -----
+    /// - if the length exceeds [GrowableDeviceMemory::max_len]
+    ///
+    /// [GrowableDeviceMemory::max_len]: #method.max_len
+    pub fn push_from(&mut self, src: &[T]) -> Result<()> {
+        if src.is_empty() {
+            return Ok(());
+        }
+        self.reserve(src.len())?;
+        let tail = self.ptr + (self.len * T::size_of()) as CUdeviceptr;
+        unsafe {
+            contexted_call!(
+                self,
+                cuMemcpy,
+                tail,
+                src.as_ptr() as CUdeviceptr,
+                src.len() * T::size_of()
+            )
+        }?;
+        self.len += src.len();
+        Ok(())
+    }
+
+    /// Copy pushed elements into a newly allocated `Vec`
+    pub fn to_vec(&self) -> Result<Vec<T>> {
+        let mut vec = Vec::with_capacity(self.len);
+        if self.len > 0 {
+            unsafe {
+                contexted_call!(
+                    self,
+                    cuMemcpyDtoH_v2,
+                    vec.as_mut_ptr() as *mut _,
+                    self.ptr,
+                    self.len * T::size_of()
+                )
+            }?;
+        }
+        // All `len` elements are written by the copy above
+        unsafe { vec.set_len(self.len) };
+        Ok(vec)
+    }
+}
+
+impl<T: Scalar> Memory for GrowableDeviceMemory<T> {
+    type Elem = T;
+    fn head_addr(&self) -> *const T {
+        self.ptr as _
+    }
+
+    fn head_addr_mut(&mut self) -> *mut T {
+        self.ptr as _
+    }
+
+    fn num_elem(&self) -> usize {
+        self.len
+    }
+
+    fn memory_type(&self) -> MemoryType {
+        MemoryType::Device
+    }
+}
+
+fn round_up(size: usize, granularity: usize) -> usize {
+    size.div_ceil(granularity) * granularity
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_granularity() -> Result<()> {
+        let device = Device::nth(0)?;
+        let granularity = super::min_granularity(&device)?;
+        assert_ne!(granularity, 0);
+        assert!(granularity.is_power_of_two());
+        Ok(())
+    }
+
+    #[test]
+    fn grow_across_granularity() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let granularity = super::min_granularity(&device)?;
+        let per_page = granularity / std::mem::size_of::<u32>();
+        let mut mem = GrowableDeviceMemory::<u32>::new(&context, 4 * per_page)?;
+        assert_eq!(mem.max_len(), 4 * per_page);
+
+        let first: Vec<u32> = (0..per_page as u32 - 3).collect();
+        mem.push_from(&first)?;
+        assert_eq!(mem.capacity(), per_page);
+        let head = mem.head_addr();
+
+        let second: Vec<u32> = (0..10).map(|i| 1000 + i).collect();
+        mem.push_from(&second)?;
+        assert!(mem.capacity() >= 2 * per_page);
+        assert_eq!(mem.head_addr(), head);
+        assert_eq!(mem.len(), first.len() + second.len());
+
+        let expected: Vec<u32> = first.iter().chain(second.iter()).cloned().collect();
+        assert_eq!(mem.to_vec()?, expected);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Exceeds the reserved address range")]
+    #[test]
+    fn grow_over_reservation() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let granularity = super::min_granularity(&device).unwrap();
+        let mut mem = GrowableDeviceMemory::<u8>::new(&context, granularity).unwrap();
+        mem.push_from(&vec![0; granularity + 1]).unwrap();
+    }
+}
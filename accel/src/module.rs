@@ -541,7 +541,7 @@ pub struct Module {
 impl Drop for Module {
     fn drop(&mut self) {
         if let Err(e) = unsafe { contexted_call!(&self.context, cuModuleUnload, self.module) } {
-            log::error!("Failed to unload module: {:?}", e);
+            report_error("Failed to unload module", &e);
         }
     }
 }
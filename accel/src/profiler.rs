@@ -18,7 +18,7 @@ pub struct Profiler {
 impl Drop for Profiler {
     fn drop(&mut self) {
         if let Err(e) = unsafe { contexted_call!(&self.ctx, cuProfilerStop) } {
-            log::error!("Failed to stop profiling: {:?}", e);
+            error::report_error("Failed to stop profiling", &e);
         }
     }
 }
@@ -1,16 +1,42 @@
-use crate::{contexted_call, contexted_new, device::*, error::*};
+use crate::{contexted_call, contexted_new, device::*, error::*, memory::*};
 use cuda::*;
+use std::{
+    cell::RefCell,
+    ffi::c_void,
+    ptr::null_mut,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
 
 /// Handler for non-blocking CUDA Stream
 pub struct Stream {
     pub(crate) stream: CUstream,
     ctx: Context,
+    /// The stream is destroyed in `Drop` only if it is owned
+    owned: bool,
 }
 
 impl Drop for Stream {
     fn drop(&mut self) {
+        if !self.owned {
+            return;
+        }
+        // `cuStreamDestroy` returns immediately and the pending tasks continue asynchronously,
+        // which is a common bug when the stream is dropped before sync
+        #[cfg(debug_assertions)]
+        {
+            if let Err(AccelError::AsyncOperationNotReady) =
+                unsafe { contexted_call!(self, cuStreamQuery, self.stream) }
+            {
+                log::warn!(
+                    "CUDA stream is dropped with pending tasks, which continue asynchronously. \
+                     Sync the stream before dropping it"
+                );
+            }
+        }
         if let Err(e) = unsafe { contexted_call!(self, cuStreamDestroy_v2, self.stream) } {
-            log::error!("Failed to delete CUDA stream: {:?}", e);
+            report_error("Failed to delete CUDA stream", &e);
         }
     }
 }
@@ -31,19 +57,80 @@ impl Contexted for Stream {
 
 impl Stream {
     /// Create a new non-blocking CUDA stream on the current context
+    ///
+    /// This is the same as [Stream::new_non_blocking].
+    ///
+    /// [Stream::new_non_blocking]: #method.new_non_blocking
     pub fn new(ctx: &Context) -> Self {
-        let stream = unsafe {
-            contexted_new!(
-                ctx,
-                cuStreamCreate,
-                CUstream_flags::CU_STREAM_NON_BLOCKING as u32
-            )
-        }
-        .expect("Failed to create CUDA stream");
+        Self::new_non_blocking(ctx)
+    }
+
+    /// Create a new stream by `CU_STREAM_NON_BLOCKING`
+    ///
+    /// Tasks in this stream run concurrently with tasks in the default (NULL) stream.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new_non_blocking(&ctx);
+    /// assert!(stream.is_non_blocking().unwrap());
+    /// ```
+    pub fn new_non_blocking(ctx: &Context) -> Self {
+        Self::with_flags(ctx, CUstream_flags::CU_STREAM_NON_BLOCKING)
+    }
+
+    /// Create a new stream by `CU_STREAM_DEFAULT`
+    ///
+    /// Tasks in this stream are synchronized with tasks in the default (NULL) stream,
+    /// i.e. they do not run concurrently with legacy code using the default stream.
+    pub fn new_blocking(ctx: &Context) -> Self {
+        Self::with_flags(ctx, CUstream_flags::CU_STREAM_DEFAULT)
+    }
+
+    fn with_flags(ctx: &Context, flags: CUstream_flags) -> Self {
+        let stream = unsafe { contexted_new!(ctx, cuStreamCreate, flags as u32) }
+            .expect("Failed to create CUDA stream");
         Stream {
             ctx: ctx.clone(),
             stream,
+            owned: true,
+        }
+    }
+
+    /// Wrap a stream created outside of accel, e.g. by cuDNN or cuBLAS
+    ///
+    /// The returned stream does **not** own `stream`, i.e. `Drop` does not destroy it.
+    /// The creator of `stream` is responsible to destroy it after the returned stream is dropped.
+    ///
+    /// Returns [AccelError::InvalidValue] if `stream` belongs to a context other than `ctx`.
+    ///
+    /// Safety
+    /// ------
+    /// - `stream` must be a valid stream handle, and must not be destroyed
+    ///   while the returned stream exists
+    ///
+    /// [AccelError::InvalidValue]: ../error/enum.AccelError.html#variant.InvalidValue
+    pub unsafe fn from_raw(ctx: &Context, stream: CUstream) -> Result<Self> {
+        let mut owner = null_mut();
+        contexted_call!(ctx, cuStreamGetCtx, stream, &mut owner as *mut CUcontext)?;
+        if ContextRef::from_ptr(owner) != **ctx {
+            return Err(AccelError::InvalidValue {
+                message: "Stream belongs to another context".into(),
+            });
         }
+        Ok(Stream {
+            ctx: ctx.clone(),
+            stream,
+            owned: false,
+        })
+    }
+
+    /// Check if this stream is created by `CU_STREAM_NON_BLOCKING`
+    pub fn is_non_blocking(&self) -> Result<bool> {
+        let mut flags = 0;
+        unsafe { contexted_call!(self, cuStreamGetFlags, self.stream, &mut flags as *mut u32) }?;
+        Ok(flags & CUstream_flags::CU_STREAM_NON_BLOCKING as u32 != 0)
     }
 
     /// Check all tasks in this stream have been completed
@@ -61,23 +148,411 @@ impl Stream {
         Ok(())
     }
 
+    /// Enqueue copies and host callbacks in order by [Pipeline], and wait until all of them complete
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// let src = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+    /// let mut dest = DeviceMemory::<i32>::zeros(&ctx, 12);
+    /// stream
+    ///     .pipeline(|p| {
+    ///         p.copy(&mut dest, &src).then(|| println!("copy completed"));
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(dest.to_vec(), vec![1; 12]);
+    /// ```
+    ///
+    /// [Pipeline]: ./struct.Pipeline.html
+    pub fn pipeline<'env, F>(&'env self, f: F) -> Result<()>
+    where
+        F: FnOnce(&Pipeline<'env>),
+    {
+        let pipeline = Pipeline {
+            stream: self,
+            callbacks: RefCell::new(Vec::new()),
+        };
+        f(&pipeline);
+        drop(pipeline);
+        self.sync()
+    }
+
     /// Wait event to sync another stream
     pub fn wait_event(&mut self, event: &Event) {
         unsafe { contexted_call!(self, cuStreamWaitEvent, self.stream, event.event, 0) }
             .expect("Failed to register an CUDA event waiting on CUDA stream");
     }
+
+    /// Mark the current end of this stream as a point which other streams can wait for
+    ///
+    /// This records an [Event] on this stream, and [Stream::wait] makes another stream wait for it.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let a = Stream::new(&ctx);
+    /// let mut b = Stream::new(&ctx);
+    /// // enqueue tasks into `a`
+    /// let point = a.sync_point();
+    /// b.wait(&point);
+    /// // tasks enqueued into `b` start after the tasks in `a` before `point`
+    /// ```
+    ///
+    /// [Event]: ./struct.Event.html
+    /// [Stream::wait]: #method.wait
+    pub fn sync_point(&self) -> SyncPoint {
+        let event = Event::new(self.ctx.clone());
+        unsafe { contexted_call!(self, cuEventRecord, event.event, self.stream) }
+            .expect("Failed to set event record");
+        SyncPoint { event }
+    }
+
+    /// Wait until tasks enqueued into this stream so far complete, or `timeout` elapses
+    ///
+    /// This records an event on the stream, and polls it by `cuEventQuery`
+    /// with exponential backoff until the deadline.
+    /// Returns `false` on timeout, while the tasks keep running.
+    /// Only this stream is waited, e.g. use [ContextOwned::default_stream] for the default stream,
+    /// since the tasks on other non-blocking streams are not ordered with it.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # use std::time::Duration;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = Stream::new(&ctx);
+    /// assert!(stream.sync_timeout(Duration::from_secs(1)).unwrap());
+    /// ```
+    ///
+    /// [ContextOwned::default_stream]: ../device/struct.ContextOwned.html#method.default_stream
+    pub fn sync_timeout(&self, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        let point = self.sync_point();
+        let mut wait = Duration::from_micros(50);
+        loop {
+            if point.query() {
+                return Ok(true);
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(false);
+            }
+            thread::sleep(wait.min(deadline - now));
+            wait = (wait * 2).min(Duration::from_millis(10));
+        }
+    }
+
+    /// Make tasks enqueued into this stream after this call wait for `point` of another stream
+    pub fn wait(&mut self, point: &SyncPoint) {
+        self.wait_event(&point.event);
+    }
+}
+
+impl ContextOwned {
+    /// Get the default (NULL) stream of this context
+    ///
+    /// The default stream always exists while the context is alive,
+    /// and this function does not allocate anything.
+    /// `Drop` of the returned stream does nothing.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let stream = ctx.default_stream();
+    /// stream.sync().unwrap();
+    /// ```
+    pub fn default_stream(self: &Arc<Self>) -> Stream {
+        Stream {
+            stream: null_mut(),
+            ctx: self.clone(),
+            owned: false,
+        }
+    }
+}
+
+type HostCallback<'a> = Option<Box<dyn FnOnce() + Send + 'a>>;
+
+unsafe extern "C" fn call_host_callback(data: *mut c_void) {
+    let callback = &mut *(data as *mut HostCallback);
+    if let Some(f) = callback.take() {
+        f();
+    }
+}
+
+/// Sequence of copies and host callbacks executed in order on a stream, created by [Stream::pipeline]
+///
+/// Each step is enqueued onto the stream when it is added,
+/// and a callback added by [Pipeline::then] runs after all preceding copies complete
+/// and before any following copy starts.
+///
+/// The pipeline is only lent to the closure of [Stream::pipeline],
+/// which waits until all steps complete before returning.
+/// Since it cannot be leaked, e.g. by `std::mem::forget`,
+/// the borrowed memories and callbacks are always alive while the steps run.
+///
+/// [Stream::pipeline]: ./struct.Stream.html#method.pipeline
+/// [Pipeline::then]: ./struct.Pipeline.html#method.then
+pub struct Pipeline<'env> {
+    stream: &'env Stream,
+    // Boxed to keep the addresses passed to `cuLaunchHostFunc` stable while the vector grows
+    #[allow(clippy::vec_box)]
+    callbacks: RefCell<Vec<Box<HostCallback<'env>>>>,
+}
+
+impl Drop for Pipeline<'_> {
+    fn drop(&mut self) {
+        // Reached also by unwinding from the closure of `Stream::pipeline`
+        if let Err(e) = self.stream.sync() {
+            report_error("Failed to sync stream of pipeline", &e);
+        }
+    }
+}
+
+impl<'env> Pipeline<'env> {
+    /// Enqueue an asynchronous copy from `src` to `dest`
+    ///
+    /// Panic
+    /// -----
+    /// - if the sizes of `dest` and `src` are different
+    pub fn copy<T: Scalar>(&self, dest: &'env mut [T], src: &'env [T]) -> &Self {
+        assert_same_size(dest.len(), src.len());
+        unsafe {
+            contexted_call!(
+                self.stream,
+                cuMemcpyAsync,
+                dest.as_mut_ptr() as CUdeviceptr,
+                src.as_ptr() as CUdeviceptr,
+                dest.len() * T::size_of(),
+                self.stream.stream
+            )
+        }
+        .expect("Failed to enqueue copy onto stream");
+        self
+    }
+
+    /// Enqueue a host callback
+    ///
+    /// The callback must not call any CUDA API.
+    pub fn then<F: FnOnce() + Send + 'env>(&self, f: F) -> &Self {
+        let mut callback: Box<HostCallback<'env>> = Box::new(Some(Box::new(f)));
+        unsafe {
+            contexted_call!(
+                self.stream,
+                cuLaunchHostFunc,
+                self.stream.stream,
+                Some(call_host_callback),
+                callback.as_mut() as *mut HostCallback as *mut c_void
+            )
+        }
+        .expect("Failed to enqueue host callback onto stream");
+        self.callbacks.borrow_mut().push(callback);
+        self
+    }
+}
+
+/// Double-buffered host-to-device transfer overlapping with processing
+///
+/// This holds two page-locked staging buffers, two device buffers and two streams.
+/// While the `n`-th chunk is processed on the host thread,
+/// the `n+1`-th chunk is transferred to the other device buffer asynchronously.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let data: Vec<i32> = (0..100).collect();
+/// let mut buffer = DoubleBuffer::new(&ctx, 30);
+/// let mut sum = 0;
+/// buffer
+///     .process(data.chunks(30), |chunk| sum += chunk.iter().sum::<i32>())
+///     .unwrap();
+/// assert_eq!(sum, data.iter().sum());
+/// ```
+pub struct DoubleBuffer<T> {
+    staging: [PageLockedMemory<T>; 2],
+    device: [DeviceMemory<T>; 2],
+    streams: [Stream; 2],
+    chunk_size: usize,
+}
+
+impl<T: Scalar> DoubleBuffer<T> {
+    /// Allocate buffers for chunks up to `chunk_size` elements
+    ///
+    /// Panic
+    /// -----
+    /// - if `chunk_size` is zero
+    pub fn new(ctx: &Context, chunk_size: usize) -> Self {
+        let staging = unsafe {
+            [
+                PageLockedMemory::uninitialized(ctx, chunk_size),
+                PageLockedMemory::uninitialized(ctx, chunk_size),
+            ]
+        };
+        let device = unsafe {
+            [
+                DeviceMemory::uninitialized(ctx, chunk_size),
+                DeviceMemory::uninitialized(ctx, chunk_size),
+            ]
+        };
+        DoubleBuffer {
+            staging,
+            device,
+            streams: [Stream::new(ctx), Stream::new(ctx)],
+            chunk_size,
+        }
+    }
+
+    /// Maximum number of elements in a chunk
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Transfer `chunks` to the device in order, and call `f` for each transferred chunk
+    ///
+    /// `f` receives the device memory of the chunk after its transfer completes.
+    /// The transfer of the next chunk runs while `f` is processing the current one,
+    /// hence `f` must complete all its works on the given chunk before returning.
+    ///
+    /// Panic
+    /// -----
+    /// - if any chunk is longer than [DoubleBuffer::chunk_size]
+    ///
+    /// [DoubleBuffer::chunk_size]: ./struct.DoubleBuffer.html#method.chunk_size
+    pub fn process<'a, I, F>(&mut self, chunks: I, f: F) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a [T]>,
+        F: FnMut(&[T]),
+        T: 'a,
+    {
+        self.run(chunks, f, |_| {})
+    }
+
+    /// [DoubleBuffer::process] reporting progress after each chunk is processed
+    ///
+    /// `progress` receives `(bytes_done, bytes_total)`,
+    /// and `bytes_done` equals to `bytes_total` at the last call.
+    ///
+    /// ```
+    /// # use accel::*;
+    /// # let device = Device::nth(0).unwrap();
+    /// # let ctx = device.create_context();
+    /// let data = vec![1_u8; 100];
+    /// let mut buffer = DoubleBuffer::new(&ctx, 30);
+    /// buffer
+    ///     .process_with_progress(data.chunks(30), |_chunk| {}, |done, total| {
+    ///         println!("{}/{} bytes", done, total)
+    ///     })
+    ///     .unwrap();
+    /// ```
+    ///
+    /// The iterator of chunks is cloned to compute `bytes_total` in advance,
+    /// which is cheap for iterators over slices, e.g. `slice::Chunks`.
+    ///
+    /// [DoubleBuffer::process]: ./struct.DoubleBuffer.html#method.process
+    pub fn process_with_progress<'a, I, F, P>(
+        &mut self,
+        chunks: I,
+        f: F,
+        mut progress: P,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a [T]>,
+        I::IntoIter: Clone,
+        F: FnMut(&[T]),
+        P: FnMut(usize, usize),
+        T: 'a,
+    {
+        let chunks = chunks.into_iter();
+        let total = chunks.clone().map(|chunk| chunk.len()).sum::<usize>() * T::size_of();
+        self.run(chunks, f, |done| progress(done, total))
+    }
+
+    /// Body of [DoubleBuffer::process], calling `progress` with bytes already processed
+    fn run<'a, I, F, P>(&mut self, chunks: I, mut f: F, mut progress: P) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a [T]>,
+        F: FnMut(&[T]),
+        P: FnMut(usize),
+        T: 'a,
+    {
+        let mut done = 0;
+        let mut chunks = chunks.into_iter();
+        let mut current = match chunks.next() {
+            Some(chunk) => self.enqueue(0, chunk),
+            None => return Ok(()),
+        };
+        let mut slot = 0;
+        loop {
+            let next = chunks.next().map(|chunk| self.enqueue(1 - slot, chunk));
+            self.streams[slot].sync()?;
+            f(&self.device[slot][..current]);
+            done += current * T::size_of();
+            progress(done);
+            match next {
+                Some(len) => {
+                    current = len;
+                    slot = 1 - slot;
+                }
+                None => return Ok(()),
+            }
+        }
+    }
+
+    /// Stage `chunk` and enqueue its transfer into the `slot`-th device buffer
+    fn enqueue(&mut self, slot: usize, chunk: &[T]) -> usize {
+        assert!(
+            chunk.len() <= self.chunk_size,
+            "Chunk is larger than the buffer"
+        );
+        self.staging[slot][..chunk.len()].copy_from_slice(chunk);
+        unsafe {
+            contexted_call!(
+                &self.streams[slot],
+                cuMemcpyHtoDAsync_v2,
+                self.device[slot].head_addr_mut() as CUdeviceptr,
+                self.staging[slot].as_ptr() as *const c_void,
+                chunk.len() * T::size_of(),
+                self.streams[slot].stream
+            )
+        }
+        .expect("Failed to enqueue copy into device buffer");
+        chunk.len()
+    }
+}
+
+/// Point in a stream created by [Stream::sync_point]
+///
+/// [Stream::sync_point]: ./struct.Stream.html#method.sync_point
+pub struct SyncPoint {
+    event: Event,
+}
+
+impl SyncPoint {
+    /// Check if tasks before this point have completed
+    pub fn query(&self) -> bool {
+        self.event.query()
+    }
+
+    /// Block the host until tasks before this point complete
+    pub fn sync(&self) -> Result<()> {
+        self.event.sync()
+    }
 }
 
 #[derive(Contexted)]
 pub struct Event {
-    event: CUevent,
+    pub(crate) event: CUevent,
     ctx: Context,
 }
 
 impl Drop for Event {
     fn drop(&mut self) {
         if let Err(e) = unsafe { contexted_call!(self, cuEventDestroy_v2, self.event) } {
-            log::error!("Failed to delete CUDA event: {:?}", e);
+            report_error("Failed to delete CUDA event", &e);
         }
     }
 }
@@ -114,6 +589,23 @@ impl Event {
         unsafe { contexted_call!(self, cuEventSynchronize, self.event) }?;
         Ok(())
     }
+
+    /// Elapsed time in milliseconds from this event to `end`
+    ///
+    /// Both events must have been recorded and completed.
+    pub fn elapsed_time(&self, end: &Event) -> Result<f32> {
+        let mut ms: f32 = 0.0;
+        unsafe {
+            contexted_call!(
+                self,
+                cuEventElapsedTime,
+                &mut ms as *mut f32,
+                self.event,
+                end.event
+            )
+        }?;
+        Ok(ms)
+    }
 }
 
 #[cfg(test)]
@@ -140,4 +632,195 @@ mod tests {
         stream.sync()?;
         Ok(())
     }
+
+    #[test]
+    fn default_stream() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let mut stream = ctx.default_stream();
+        assert!(stream.stream.is_null());
+        let mut event = Event::new(ctx.clone());
+        event.record(&mut stream);
+        stream.sync()?;
+        assert!(event.query());
+        assert!(stream.query());
+        drop(stream); // must not destroy the default stream
+        ctx.default_stream().sync()?;
+        Ok(())
+    }
+
+    /// Enqueue a host callback sleeping `ms` milliseconds to keep `stream` busy
+    fn enqueue_sleep(stream: &Stream, ms: usize) -> Result<()> {
+        unsafe extern "C" fn sleep(data: *mut c_void) {
+            thread::sleep(Duration::from_millis(data as u64));
+        }
+        unsafe {
+            contexted_call!(
+                stream,
+                cuLaunchHostFunc,
+                stream.stream,
+                Some(sleep),
+                ms as *mut c_void
+            )
+        }
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn drop_with_pending_tasks() -> Result<()> {
+        drop(captured_warnings());
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let stream = Stream::new(&ctx);
+        enqueue_sleep(&stream, 200)?;
+        drop(stream);
+        ctx.sync()?;
+        // Other tests running in parallel may also be captured
+        assert!(captured_warnings()
+            .iter()
+            .any(|w| w.contains("dropped with pending tasks")));
+        Ok(())
+    }
+
+    #[test]
+    fn pipeline() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let stream = Stream::new(&ctx);
+        let first = PageLockedMemory::<i32>::from_elem(&ctx, 12, 1);
+        let second = PageLockedMemory::<i32>::from_elem(&ctx, 12, 2);
+        let mut a = PageLockedMemory::<i32>::zeros(&ctx, 12);
+        let mut b = PageLockedMemory::<i32>::zeros(&ctx, 12);
+        let order = std::sync::Mutex::new(Vec::new());
+        stream.pipeline(|p| {
+            p.copy(&mut a, &first)
+                .then(|| order.lock().unwrap().push(1))
+                .copy(&mut b, &second)
+                .then(|| order.lock().unwrap().push(2));
+        })?;
+        assert_eq!(*order.lock().unwrap(), vec![1, 2]);
+        assert_eq!(a.to_vec(), vec![1; 12]);
+        assert_eq!(b.to_vec(), vec![2; 12]);
+        Ok(())
+    }
+
+    #[test]
+    fn sync_timeout() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let stream = Stream::new(&ctx);
+        enqueue_sleep(&stream, 500)?;
+        assert!(!stream.sync_timeout(Duration::from_millis(10))?);
+        assert!(stream.sync_timeout(Duration::from_secs(10))?);
+        assert!(stream.query());
+        Ok(())
+    }
+
+    #[test]
+    fn sync_point() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let a = Stream::new(&ctx);
+        let mut b = Stream::new(&ctx);
+        let dim: Ix2 = (1 << 10, 1 << 8).into();
+        let data: Vec<i32> = (0..dim.len() as i32).collect();
+        let src = Array::<i32, Ix2>::from_slice(&ctx, dim, &data);
+        let mut mid = Array::<i32, Ix2>::zeros(&ctx, dim);
+        let mut dest = Array::<i32, Ix2>::zeros(&ctx, dim);
+        mid.copy_from_async(&src, &a);
+        let point = a.sync_point();
+        b.wait(&point);
+        // This copy on `b` must start after the copy on `a`
+        dest.copy_from_async(&mid, &b);
+        b.sync()?;
+        assert!(point.query());
+        assert_eq!(dest.to_vec(), data);
+        Ok(())
+    }
+
+    #[test]
+    fn from_raw() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let raw = unsafe {
+            contexted_new!(
+                &ctx,
+                cuStreamCreate,
+                CUstream_flags::CU_STREAM_NON_BLOCKING as u32
+            )
+        }?;
+        {
+            let stream = unsafe { Stream::from_raw(&ctx, raw) }?;
+            let mem = DeviceMemory::<i32>::from_elem(&ctx, 12, 3);
+            let host = mem.download_async(&stream).wait()?;
+            assert_eq!(host.as_slice(), &[3; 12]);
+        }
+        // The raw stream is still alive after the wrapper is dropped
+        unsafe { contexted_call!(&ctx, cuStreamSynchronize, raw) }?;
+        unsafe { contexted_call!(&ctx, cuStreamDestroy_v2, raw) }?;
+
+        let other = device.create_context();
+        let stream = Stream::new(&ctx);
+        let result = unsafe { Stream::from_raw(&other, stream.stream) };
+        assert!(matches!(result, Err(AccelError::InvalidValue { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn non_blocking() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let stream = Stream::new_non_blocking(&ctx);
+        assert!(stream.is_non_blocking()?);
+        assert!(Stream::new(&ctx).is_non_blocking()?);
+        assert!(!Stream::new_blocking(&ctx).is_non_blocking()?);
+
+        // Upload on the non-blocking stream while the default stream also has work
+        let src: Vec<i32> = (0..1024).collect();
+        let upload_a = DeviceMemory::upload_async(&ctx, &src, &stream);
+        let default = ctx.default_stream();
+        let upload_b = DeviceMemory::upload_async(&ctx, &src, &default);
+        let a = upload_a.wait()?;
+        let b = upload_b.wait()?;
+        assert_eq!(a.as_slice(), src.as_slice());
+        assert_eq!(b.as_slice(), src.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn double_buffer() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let data: Vec<i32> = (0..14).collect();
+        let mut buffer = DoubleBuffer::new(&ctx, 4);
+        let mut delivered = Vec::new();
+        let mut count = 0;
+        buffer.process(data.chunks(4), |chunk| {
+            delivered.extend_from_slice(chunk);
+            count += 1;
+        })?;
+        assert_eq!(count, 4);
+        assert_eq!(delivered, data);
+        Ok(())
+    }
+
+    #[test]
+    fn double_buffer_progress() -> Result<()> {
+        let device = Device::nth(0)?;
+        let ctx = device.create_context();
+        let data: Vec<i32> = (0..14).collect();
+        let mut buffer = DoubleBuffer::new(&ctx, 4);
+        let mut reports = Vec::new();
+        buffer.process_with_progress(
+            data.chunks(4),
+            |_chunk| {},
+            |done, total| reports.push((done, total)),
+        )?;
+        let total = 14 * std::mem::size_of::<i32>();
+        assert_eq!(
+            reports,
+            vec![(16, total), (32, total), (48, total), (56, total)]
+        );
+        Ok(())
+    }
 }
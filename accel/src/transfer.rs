@@ -0,0 +1,268 @@
+//! High-level host-to-device transfer
+//!
+//! A copy from pageable host memory is staged through a driver-internal pinned buffer,
+//! which is much slower than a copy from page-locked memory for large data.
+//! On the other hand, registering host memory as page-locked has a fixed cost,
+//! which does not pay off for small data.
+//! Functions in this module choose the faster way based on the size of data.
+
+use crate::{error::*, *};
+use cuda::*;
+use std::ffi::c_void;
+
+/// Data larger than this size in bytes is copied via a temporary registration
+pub const REGISTER_THRESHOLD: usize = 1 << 20;
+
+/// Upper bound of the chunk size in bytes recommended by [recommended_chunk_size]
+///
+/// [recommended_chunk_size]: ./fn.recommended_chunk_size.html
+pub const MAX_CHUNK_SIZE: usize = 4 << 20;
+
+/// Recommended size in bytes of a chunk to transfer `total_bytes` via a page-locked staging buffer
+///
+/// A larger chunk amortizes the overhead of each copy, while a smaller chunk keeps
+/// the footprint of page-locked memory small, which is a limited resource of the system.
+/// The chunk is [MAX_CHUNK_SIZE] bytes, reduced to 1/256 of the memory of `device` for small devices,
+/// and clamped to `total_bytes`. This returns at least one byte.
+///
+/// ```
+/// # use accel::*;
+/// let device = Device::nth(0).unwrap();
+/// let size = transfer::recommended_chunk_size(1 << 30, &device);
+/// assert!(size <= transfer::MAX_CHUNK_SIZE);
+/// assert_eq!(transfer::recommended_chunk_size(1000, &device), 1000);
+/// ```
+///
+/// [MAX_CHUNK_SIZE]: ./constant.MAX_CHUNK_SIZE.html
+pub fn recommended_chunk_size(total_bytes: usize, device: &Device) -> usize {
+    let bytes = match device.total_memory() {
+        Ok(memory) => (memory / 256).clamp(1, MAX_CHUNK_SIZE),
+        Err(_) => MAX_CHUNK_SIZE,
+    };
+    bytes.min(total_bytes).max(1)
+}
+
+/// Recommended number of elements of a chunk to transfer `total_len` elements
+///
+/// This is [recommended_chunk_size] in the unit of elements,
+/// and returns at least one element, so that it can be used as `chunk_len`
+/// of [Continuous::copy_from_chunked] as is.
+///
+/// ```
+/// # use accel::*;
+/// # use std::sync::atomic::AtomicBool;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let src = vec![1_f32; 1 << 22];
+/// let mut dest = DeviceMemory::<f32>::zeros(&ctx, src.len());
+/// let chunk_len = transfer::recommended_chunk_len::<f32>(src.len(), &device);
+/// assert!(chunk_len * std::mem::size_of::<f32>() <= transfer::MAX_CHUNK_SIZE);
+/// dest.copy_from_chunked(src.as_slice(), chunk_len, &AtomicBool::new(false));
+/// ```
+///
+/// [recommended_chunk_size]: ./fn.recommended_chunk_size.html
+/// [Continuous::copy_from_chunked]: ../memory/trait.Continuous.html#method.copy_from_chunked
+pub fn recommended_chunk_len<T: Scalar>(total_len: usize, device: &Device) -> usize {
+    (recommended_chunk_size(total_len * T::size_of(), device) / T::size_of()).max(1)
+}
+
+/// Default of `chunk_len` for [Continuous::copy_from_chunked] between `dest` and `src`
+///
+/// The device is found from `dest` or `src`. Without any device, e.g. for a copy between
+/// host memories, the chunk is [MAX_CHUNK_SIZE] bytes.
+///
+/// [Continuous::copy_from_chunked]: ../memory/trait.Continuous.html#method.copy_from_chunked
+/// [MAX_CHUNK_SIZE]: ./constant.MAX_CHUNK_SIZE.html
+pub(crate) fn default_chunk_len<T: Scalar>(dest: &[T], src: &[T]) -> usize {
+    let device = device_ordinal(dest.as_ptr())
+        .or_else(|_| device_ordinal(src.as_ptr()))
+        .and_then(|ordinal| Device::nth(ordinal as usize));
+    match device {
+        Ok(device) => recommended_chunk_len::<T>(src.len(), &device),
+        Err(_) => (MAX_CHUNK_SIZE / T::size_of()).min(src.len()).max(1),
+    }
+}
+
+/// Copy host data into a newly allocated device memory
+///
+/// If `data` is larger than [REGISTER_THRESHOLD] bytes, it is temporarily registered
+/// as page-locked memory by [cuMemHostRegister] during the copy.
+/// Otherwise, or if the registration fails, it is copied as pageable memory.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let data = vec![1_u32; 1024];
+/// let mem = transfer::to_device(&ctx, &data);
+/// assert_eq!(mem.as_slice(), data.as_slice());
+/// ```
+///
+/// Panic
+/// -----
+/// - if `data` is empty
+///
+/// [REGISTER_THRESHOLD]: ./constant.REGISTER_THRESHOLD.html
+/// [cuMemHostRegister]: https://docs.nvidia.com/cuda/cuda-driver-api/group__CUDA__MEM.html
+pub fn to_device<T: Scalar>(context: &Context, data: &[T]) -> DeviceMemory<T> {
+    let mut mem = unsafe { DeviceMemory::uninitialized(context, data.len()) };
+    let bytes = data.len() * T::size_of();
+    if bytes > REGISTER_THRESHOLD && copy_registered(context, &mut mem, data).is_ok() {
+        return mem;
+    }
+    mem.copy_from(data);
+    mem
+}
+
+/// Copy `src` into `dest` while `src` is registered as page-locked memory
+fn copy_registered<T: Scalar>(
+    context: &Context,
+    dest: &mut DeviceMemory<T>,
+    src: &[T],
+) -> Result<()> {
+    let bytes = src.len() * T::size_of();
+    // The driver only reads the registered memory since it is used as a source of copy
+    let host = src.as_ptr() as *mut c_void;
+    unsafe { contexted_call!(context, cuMemHostRegister_v2, host, bytes, 0) }?;
+    let result = unsafe {
+        contexted_call!(
+            context,
+            cuMemcpyHtoD_v2,
+            dest.head_addr_mut() as CUdeviceptr,
+            host,
+            bytes
+        )
+    };
+    if let Err(e) = unsafe { contexted_call!(context, cuMemHostUnregister, host) } {
+        error::report_error("Failed to unregister memory", &e);
+    }
+    result
+}
+
+/// Copy a row-major matrix of `rows` x `cols` from `src` into `dest` as its transpose,
+/// i.e. `dest` becomes a row-major matrix of `cols` x `rows`
+///
+/// This also converts a row-major matrix into column-major one, and vice versa.
+/// `cuMemcpy2D` only copies rows with pitches and cannot transpose elements,
+/// so the matrix is transposed in a host buffer and copied by [Memcpy] between `src` and `dest`.
+///
+/// ```
+/// # use accel::*;
+/// # let device = Device::nth(0).unwrap();
+/// # let ctx = device.create_context();
+/// let src = [1, 2, 3,
+///            4, 5, 6];
+/// let mut dest = DeviceMemory::<i32>::zeros(&ctx, 6);
+/// transfer::copy_transposed(&mut dest, &src, 2, 3);
+/// assert_eq!(dest.as_slice(), &[1, 4,
+///                               2, 5,
+///                               3, 6]);
+/// ```
+///
+/// Panic
+/// -----
+/// - if the length of `src` or `dest` is not equal to `rows * cols`
+///
+/// [Memcpy]: ../memory/trait.Memcpy.html
+pub fn copy_transposed<T, Dest, Src>(dest: &mut Dest, src: &Src, rows: usize, cols: usize)
+where
+    T: Scalar,
+    Dest: Continuous<Elem = T> + ?Sized,
+    Src: Continuous<Elem = T> + ?Sized,
+{
+    let n = rows * cols;
+    assert_eq!(src.num_elem(), n, "Source size mismatches with the matrix");
+    assert_eq!(
+        dest.num_elem(),
+        n,
+        "Destination size mismatches with the matrix"
+    );
+    let mut staging = vec![T::zero(); n];
+    staging.as_mut_slice().copy_from(src.as_slice());
+    let mut transposed = vec![T::zero(); n];
+    for i in 0..rows {
+        for j in 0..cols {
+            transposed[j * rows + i] = staging[i * cols + j];
+        }
+    }
+    dest.as_mut_slice().copy_from(transposed.as_slice());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_device_tiny() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let data = vec![1_i32, 2, 3];
+        let mem = to_device(&context, &data);
+        assert_eq!(mem.as_slice(), data.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn to_device_large() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let n = 2 * REGISTER_THRESHOLD / std::mem::size_of::<u32>() + 7;
+        let data: Vec<u32> = (0..n as u32).collect();
+        let mem = to_device(&context, &data);
+        assert_eq!(mem.as_slice(), data.as_slice());
+        Ok(())
+    }
+
+    #[test]
+    fn recommended_chunk_len() -> Result<()> {
+        let device = Device::nth(0)?;
+        let len = super::recommended_chunk_len::<u32>(1 << 30, &device);
+        assert!(len * 4 >= 1 << 20);
+        assert!(len * 4 <= MAX_CHUNK_SIZE);
+        assert_eq!(super::recommended_chunk_len::<u32>(1000, &device), 1000);
+        assert_eq!(super::recommended_chunk_len::<u32>(0, &device), 1);
+        assert_eq!(super::recommended_chunk_len::<Float4>(1, &device), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn recommended_chunk_size() -> Result<()> {
+        let device = Device::nth(0)?;
+        let size = super::recommended_chunk_size(1 << 30, &device);
+        assert!(size >= 1 << 20);
+        assert!(size <= MAX_CHUNK_SIZE);
+        assert_eq!(super::recommended_chunk_size(1000, &device), 1000);
+        assert_eq!(super::recommended_chunk_size(0, &device), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn copy_transposed() -> Result<()> {
+        let device = Device::nth(0)?;
+        let context = device.create_context();
+        let (rows, cols) = (3, 4);
+        let src: Vec<u32> = (0..(rows * cols) as u32).collect();
+        let mut mem = DeviceMemory::<u32>::zeros(&context, rows * cols);
+        super::copy_transposed(&mut mem, &src, rows, cols);
+        for i in 0..rows {
+            for j in 0..cols {
+                assert_eq!(mem[j * rows + i], src[i * cols + j]);
+            }
+        }
+
+        // Transpose back into host memory
+        let mut host = vec![0_u32; rows * cols];
+        super::copy_transposed(&mut host, &mem, cols, rows);
+        assert_eq!(host, src);
+        Ok(())
+    }
+
+    #[should_panic(expected = "Source size mismatches with the matrix")]
+    #[test]
+    fn copy_transposed_mismatch() {
+        let device = Device::nth(0).unwrap();
+        let context = device.create_context();
+        let mut mem = DeviceMemory::<u32>::zeros(&context, 12);
+        super::copy_transposed(&mut mem, &[0_u32; 10], 3, 4);
+    }
+}
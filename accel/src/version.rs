@@ -0,0 +1,59 @@
+//! Version of CUDA driver
+//!
+//! CUDA encodes a version into an integer as `1000 * major + 10 * minor`, e.g. `10020` for CUDA 10.2.
+
+use crate::{error::*, *};
+use cuda::*;
+
+/// Get the latest CUDA version supported by the installed driver
+///
+/// ```
+/// let version = accel::version::driver_version().unwrap();
+/// println!("CUDA driver version = {}", version);
+/// ```
+pub fn driver_version() -> Result<u32> {
+    let mut version: i32 = 0;
+    unsafe { ffi_call!(cuDriverGetVersion, &mut version as *mut i32) }?;
+    Ok(version as u32)
+}
+
+/// Get the latest CUDA version supported by the installed driver as `(major, minor)`
+///
+/// ```
+/// let (major, minor) = accel::version::driver_major_minor().unwrap();
+/// println!("CUDA driver version = {}.{}", major, minor);
+/// ```
+pub fn driver_major_minor() -> Result<(u32, u32)> {
+    Ok(parse_version(driver_version()?))
+}
+
+/// Split encoded version into `(major, minor)`
+///
+/// ```
+/// use accel::version::parse_version;
+/// assert_eq!(parse_version(10020), (10, 2));
+/// assert_eq!(parse_version(11000), (11, 0));
+/// ```
+pub fn parse_version(version: u32) -> (u32, u32) {
+    (version / 1000, (version % 1000) / 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn driver_version() -> Result<()> {
+        let version = super::driver_version()?;
+        assert!(version >= 10000);
+        let (major, _minor) = driver_major_minor()?;
+        assert!(major >= 10);
+        Ok(())
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_version(10020), (10, 2));
+        assert_eq!(parse_version(9000), (9, 0));
+    }
+}
@@ -0,0 +1,83 @@
+use accel::num_traits::Zero;
+use accel::*;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Scalar)]
+struct Rgba {
+    r: u32,
+    g: u32,
+    b: u32,
+    a: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Scalar)]
+struct Rgba8 {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Scalar)]
+struct Pair16 {
+    x: u16,
+    y: u16,
+}
+
+#[test]
+fn derive_scalar() -> error::Result<()> {
+    assert_eq!(Rgba::size_of(), 16);
+    assert_eq!(Rgba::channels(), 4);
+    assert_eq!(Rgba::format(), u32::format());
+
+    let device = Device::nth(0)?;
+    let ctx = device.create_context();
+    let value = Rgba {
+        r: 1,
+        g: 2,
+        b: 3,
+        a: 4,
+    };
+    let mem = DeviceMemory::<Rgba>::from_elem(&ctx, 12, value);
+    assert_eq!(mem.num_elem(), 12);
+    assert_eq!(mem.to_vec(), vec![value; 12]);
+    Ok(())
+}
+
+#[test]
+fn derive_small_scalar() -> error::Result<()> {
+    assert_eq!(Rgba8::size_of(), 4);
+    assert_eq!(Rgba8::channels(), 4);
+    assert_eq!(Pair16::size_of(), 4);
+    assert_eq!(Pair16::channels(), 2);
+
+    let device = Device::nth(0)?;
+    let ctx = device.create_context();
+    let mut mem = DeviceMemory::<Rgba8>::zeros(&ctx, 12);
+    assert_eq!(mem.to_vec(), vec![Rgba8::zero(); 12]);
+    let value = Rgba8 {
+        r: 1,
+        g: 2,
+        b: 3,
+        a: 4,
+    };
+    mem.set(value);
+    assert_eq!(mem.to_vec(), vec![value; 12]);
+
+    let value = Pair16 { x: 1, y: 0x100 };
+    let mem = DeviceMemory::<Pair16>::from_elem(&ctx, 12, value);
+    assert_eq!(mem.to_vec(), vec![value; 12]);
+    Ok(())
+}
+
+#[should_panic(expected = "Numbers of channels of array elements mismatch")]
+#[test]
+fn copy_from_array_channels_mismatch() {
+    let device = Device::nth(0).unwrap();
+    let ctx = device.create_context();
+    let src = Array::<Rgba8, Ix1>::zeros(&ctx, 4.into());
+    let mut dest = Array::<u32, Ix1>::zeros(&ctx, 4.into());
+    dest.copy_from_array(&src);
+}